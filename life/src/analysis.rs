@@ -0,0 +1,213 @@
+use crate::automaton::Automaton;
+use crate::game::Game;
+use crate::generation::Generation;
+use crate::period::{Cycle, PeriodDetector};
+use crate::rule::Rule;
+use std::fmt;
+
+type Grid = Vec<Vec<bool>>;
+
+// Space left around the pattern so that it can grow a cell each generation.
+const MARGIN: usize = 2;
+
+/// What a pattern turns into, as found by `analyze`.
+#[derive(Debug, PartialEq)]
+pub enum Class {
+    DiesOut,
+    StillLife,
+    Oscillator,
+    Spaceship,
+    Unknown,
+}
+
+/// The result of running a pattern until it repeats. Heat is the average
+/// number of cells changing each generation of the cycle and volatility the
+/// share of an oscillator's cells which ever change.
+#[derive(Debug, PartialEq)]
+pub struct Analysis {
+    pub class: Class,
+    pub cycle: Option<Cycle>,
+    pub heat: Option<f64>,
+    pub volatility: Option<f64>,
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Writes a speed the usual way, such as c/4 diagonal, 2c/5 orthogonal or
+/// (2,1)c/6 oblique.
+pub fn speed((dx, dy): (isize, isize), period: u64) -> String {
+    let (long, short) = (dx.unsigned_abs().max(dy.unsigned_abs()) as u64, dx.unsigned_abs().min(dy.unsigned_abs()) as u64);
+    if short != 0 && short != long {
+        return format!("({},{})c/{} oblique", long, short, period);
+    }
+    let divisor = gcd(long, period);
+    let (numerator, denominator) = (long / divisor, period / divisor);
+    let fraction = match (numerator, denominator) {
+        (1, 1) => "c".to_owned(),
+        (1, _) => format!("c/{}", denominator),
+        (_, 1) => format!("{}c", numerator),
+        _ => format!("{}c/{}", numerator, denominator),
+    };
+    let direction = if short == 0 { "orthogonal" } else { "diagonal" };
+    format!("{} {}", fraction, direction)
+}
+
+/// Trims `generation` to its live cells plus a margin, returning it with the
+/// shift of its top left corner.
+fn recentre(generation: &Generation) -> (Generation, (isize, isize)) {
+    let (left, top, width, height) = generation.bounding_box().unwrap_or((0, 0, 0, 0));
+    let (left, top) = (left as isize - MARGIN as isize, top as isize - MARGIN as isize);
+    (generation.crop(left, top, width + 2 * MARGIN, height + 2 * MARGIN), (left, top))
+}
+
+/// Runs one period of a cycle in a grid big enough that nothing reaches its
+/// edges, measuring heat and, for oscillators, volatility.
+fn measure(generation: &Generation, rule: Option<Rule>, cycle: &Cycle) -> (f64, Option<f64>) {
+    let pad = cycle.period as usize + MARGIN;
+    let (left, top, width, height) = generation.bounding_box().unwrap_or((0, 0, 0, 0));
+    let start = generation.crop(left as isize - pad as isize, top as isize - pad as isize, width + 2 * pad, height + 2 * pad);
+    let mut ever_alive: Grid = start.grid.clone();
+    let mut ever_changed: Grid = vec![vec![false; start.grid[0].len()]; start.grid.len()];
+    let mut changes = 0;
+    let mut game = Game::new(start, rule);
+    for _ in 0..cycle.period {
+        let previous = game.generation().grid.clone();
+        game.step();
+        for (y, row) in game.generation().grid.iter().enumerate() {
+            for (x, alive) in row.iter().enumerate() {
+                ever_alive[y][x] |= *alive;
+                if *alive != previous[y][x] {
+                    ever_changed[y][x] = true;
+                    changes += 1;
+                }
+            }
+        }
+    }
+    let heat = changes as f64 / cycle.period as f64;
+    let count = |grid: &Grid| grid.iter().flatten().filter(|cell| **cell).count();
+    let volatility = match cycle.displacement {
+        (0, 0) if count(&ever_alive) > 0 => Some(count(&ever_changed) as f64 / count(&ever_alive) as f64),
+        _ => None,
+    };
+    (heat, volatility)
+}
+
+/// Runs `grid` for up to `max_generations`, following it around so that it
+/// never meets the edge, until it repeats, and classifies what it became.
+pub fn analyze(grid: Grid, rule: Option<Rule>, max_generations: u64) -> Analysis {
+    let (generation, mut origin) = recentre(&Generation::new(grid));
+    let mut game = Game::new(generation, rule.clone());
+    let mut detector = PeriodDetector::new();
+    let mut count = 0;
+    let mut cycle = detector.observe_at(count, game.generation(), origin);
+    while cycle.is_none() && count < max_generations {
+        game.step();
+        count += 1;
+        let (generation, shift) = recentre(game.generation());
+        origin = (origin.0 + shift.0, origin.1 + shift.1);
+        game.reset(generation);
+        cycle = detector.observe_at(count, game.generation(), origin);
+    }
+    let Some(cycle) = cycle else {
+        return Analysis{class: Class::Unknown, cycle, heat: None, volatility: None};
+    };
+    let class = match (game.generation().population(), cycle.period, cycle.displacement) {
+        (0, _, _) => Class::DiesOut,
+        (_, 1, (0, 0)) => Class::StillLife,
+        (_, _, (0, 0)) => Class::Oscillator,
+        _ => Class::Spaceship,
+    };
+    if class == Class::DiesOut {
+        return Analysis{class, cycle: Some(cycle), heat: None, volatility: None};
+    }
+    let (heat, volatility) = measure(game.generation(), rule, &cycle);
+    Analysis{class, cycle: Some(cycle), heat: Some(heat), volatility}
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(cycle) = self.cycle else {
+            return writeln!(f, "No repetition found");
+        };
+        match self.class {
+            Class::DiesOut => write!(f, "Dies out")?,
+            Class::StillLife => write!(f, "Still life")?,
+            Class::Oscillator => write!(f, "Oscillator with period {}", cycle.period)?,
+            Class::Spaceship => write!(
+                f,
+                "Spaceship moving ({}, {}) every {} generations, {}",
+                cycle.displacement.0,
+                cycle.displacement.1,
+                cycle.period,
+                speed(cycle.displacement, cycle.period),
+            )?,
+            Class::Unknown => {}
+        }
+        match cycle.start {
+            0 => writeln!(f)?,
+            start => writeln!(f, " from generation {}", start)?,
+        }
+        if let Some(heat) = self.heat {
+            writeln!(f, "Heat {:.2}", heat)?;
+        }
+        if let Some(volatility) = self.volatility {
+            writeln!(f, "Volatility {:.2}", volatility)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rle::pattern;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("x = 2, y = 2\n2o$2o!", Class::StillLife, 1, (0, 0), 0.0, Some(0.0))]
+    #[case("x = 3, y = 1\n3o!", Class::Oscillator, 2, (0, 0), 4.0, Some(0.8))]
+    #[case("x = 3, y = 3\nbo$2bo$3o!", Class::Spaceship, 4, (1, 1), 4.0, None)]
+    #[case("x = 5, y = 4\nbo2bo$o4b$o3bo$4o!", Class::Spaceship, 4, (-2, 0), 11.0, None)]
+    fn test_analyze(
+        #[case] rle: &str,
+        #[case] class: Class,
+        #[case] period: u64,
+        #[case] displacement: (isize, isize),
+        #[case] heat: f64,
+        #[case] volatility: Option<f64>,
+    ) {
+        let analysis = analyze(pattern(rle).grid, None, 100);
+        assert_eq!(class, analysis.class);
+        let cycle = analysis.cycle.unwrap();
+        assert_eq!((period, displacement), (cycle.period, cycle.displacement));
+        assert_eq!(Some(heat), analysis.heat);
+        assert_eq!(volatility, analysis.volatility);
+    }
+
+    #[test]
+    fn test_analyze_dies_out() {
+        let analysis = analyze(pattern("x = 2, y = 1\n2o!").grid, None, 100);
+        assert_eq!(Class::DiesOut, analysis.class);
+        assert_eq!("Dies out from generation 1\n", analysis.to_string());
+    }
+
+    #[test]
+    fn test_analyze_gives_up() {
+        // The R-pentomino takes over a thousand generations to settle.
+        let analysis = analyze(pattern("x = 3, y = 3\nb2o$2o$bo!").grid, None, 50);
+        assert_eq!(Class::Unknown, analysis.class);
+        assert_eq!("No repetition found\n", analysis.to_string());
+    }
+
+    #[rstest]
+    #[case((1, 1), 4, "c/4 diagonal")]
+    #[case((-2, 0), 4, "c/2 orthogonal")]
+    #[case((0, 2), 5, "2c/5 orthogonal")]
+    #[case((1, 0), 1, "c orthogonal")]
+    #[case((2, -1), 6, "(2,1)c/6 oblique")]
+    fn test_speed(#[case] displacement: (isize, isize), #[case] period: u64, #[case] expected: &str) {
+        assert_eq!(expected, speed(displacement, period));
+    }
+}
@@ -0,0 +1,262 @@
+use crate::analysis::{self, Class};
+use crate::automaton::Automaton;
+use crate::game::Game;
+use crate::generation::Generation;
+use crate::period::Cycle;
+use crate::rule::Rule;
+
+type Grid = Vec<Vec<bool>>;
+
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+// The most empty columns one `y` can stand for.
+const MAX_GAP: usize = 4 + 35;
+
+fn digit(value: usize) -> char {
+    DIGITS[value] as char
+}
+
+fn value(digit: char) -> Option<usize> {
+    DIGITS.iter().position(|d| *d as char == digit)
+}
+
+/// Writes a grid in extended Wechsler format: strips five rows high,
+/// separated by `z`, each column a digit whose bits are its cells from the
+/// top down. Runs of empty columns are shortened to `0`, `w`, `x` or `y`
+/// followed by the number of columns past four, and dropped at the end of a
+/// strip.
+fn wechsler(grid: &Grid) -> String {
+    let width = grid.first().map_or(0, |row| row.len());
+    let mut code = String::new();
+    for (index, strip) in grid.chunks(5).enumerate() {
+        if index > 0 {
+            code.push('z');
+        }
+        let mut gap = 0;
+        for x in 0..width {
+            let column = strip.iter().enumerate().fold(0, |bits, (y, row)| bits | (row[x] as usize) << y);
+            if column == 0 {
+                gap += 1;
+                continue;
+            }
+            while gap > 0 {
+                let run = gap.min(MAX_GAP);
+                match run {
+                    1 => code.push('0'),
+                    2 => code.push('w'),
+                    3 => code.push('x'),
+                    _ => {
+                        code.push('y');
+                        code.push(digit(run - 4));
+                    }
+                }
+                gap -= run;
+            }
+            code.push(digit(column));
+        }
+    }
+    code
+}
+
+fn rotate(grid: &Grid) -> Grid {
+    let height = grid.len();
+    let width = grid.first().map_or(0, |row| row.len());
+    (0..width).map(|x| (0..height).map(|y| grid[height - 1 - y][x]).collect()).collect()
+}
+
+fn flip(grid: &Grid) -> Grid {
+    grid.iter().map(|row| row.iter().rev().copied().collect()).collect()
+}
+
+/// The grid in each of its 8 rotations and reflections, starting with the
+/// grid as it is.
+pub fn orientations(grid: &Grid) -> Vec<Grid> {
+    let mut grid = grid.clone();
+    let mut orientations = Vec::with_capacity(8);
+    for _ in 0..4 {
+        orientations.push(grid.clone());
+        orientations.push(flip(&grid));
+        grid = rotate(&grid);
+    }
+    orientations
+}
+
+/// Whether `code` comes before `other`, shorter codes first.
+fn precedes(code: &str, other: &str) -> bool {
+    (code.len(), code) < (other.len(), other)
+}
+
+/// The Wechsler code of the live cells of `generation`, the same however
+/// they are placed, rotated or reflected.
+pub fn canonical(generation: &Generation) -> String {
+    let Some((left, top, width, height)) = generation.bounding_box() else {
+        return "0".to_owned();
+    };
+    let grid = generation.crop(left as isize, top as isize, width, height).grid;
+    let mut best: Option<String> = None;
+    for orientation in orientations(&grid) {
+        let code = wechsler(&orientation);
+        if best.as_ref().is_none_or(|best| precedes(&code, best)) {
+            best = Some(code);
+        }
+    }
+    best.unwrap()
+}
+
+/// Every phase of `generation` once it has settled into `cycle`.
+pub fn phases(generation: &Generation, rule: Option<Rule>, cycle: &Cycle) -> Vec<Generation> {
+    let Some((left, top, width, height)) = generation.bounding_box() else {
+        return Vec::new();
+    };
+    let pad = (cycle.start + cycle.period) as usize + 2;
+    let start = generation.crop(left as isize - pad as isize, top as isize - pad as isize, width + 2 * pad, height + 2 * pad);
+    let mut game = Game::new(start, rule);
+    for _ in 0..cycle.start {
+        game.step();
+    }
+    (0..cycle.period).map(|_| {
+        let phase = Generation::new(game.generation().grid.clone());
+        game.step();
+        phase
+    }).collect()
+}
+
+/// The apgcode of what `generation` settles into within `max_generations`:
+/// `xs` and the population for still lifes, `xp` or `xq` and the period for
+/// oscillators and spaceships, then the canonical Wechsler code of the phase
+/// which gives the first.
+pub fn encode(generation: &Generation, rule: Option<Rule>, max_generations: u64) -> Option<String> {
+    let analysis = analysis::analyze(generation.grid.clone(), rule.clone(), max_generations);
+    let cycle = analysis.cycle?;
+    let prefix = match analysis.class {
+        Class::DiesOut => return Some("xs0_0".to_owned()),
+        Class::StillLife => "xs",
+        Class::Oscillator => "xp",
+        Class::Spaceship => "xq",
+        Class::Unknown => return None,
+    };
+    let mut best: Option<(String, usize)> = None;
+    for phase in phases(generation, rule, &cycle) {
+        let code = canonical(&phase);
+        if best.as_ref().is_none_or(|(best, _)| precedes(&code, best)) {
+            best = Some((code, phase.population()));
+        }
+    }
+    let (code, population) = best?;
+    let size = match analysis.class {
+        Class::StillLife => population as u64,
+        _ => cycle.period,
+    };
+    Some(format!("{}{}_{}", prefix, size, code))
+}
+
+/// The pattern an apgcode such as `xs4_33` or `xq4_153` stands for, cropped
+/// to its live cells.
+pub fn decode(code: &str) -> Result<Generation, &'static str> {
+    let (prefix, wechsler) = code.split_once('_').ok_or("An apgcode needs a prefix such as xs4_")?;
+    let size = prefix.strip_prefix("xs").or(prefix.strip_prefix("xp")).or(prefix.strip_prefix("xq"))
+        .ok_or("An apgcode must start with xs, xp or xq")?;
+    if size.is_empty() || !size.chars().all(|ch| ch.is_ascii_digit()) {
+        return Err("An apgcode needs a population or period after its prefix");
+    }
+    // `y` and the character after it are one token, as `yz` is a gap of 39.
+    let mut strips: Vec<Vec<usize>> = vec![Vec::new()];
+    let mut chars = wechsler.chars();
+    while let Some(ch) = chars.next() {
+        let columns = strips.last_mut().unwrap();
+        match ch {
+            'w' => columns.extend([0; 2]),
+            'x' => columns.extend([0; 3]),
+            'y' => {
+                let run = chars.next().and_then(value).ok_or("An apgcode y must be followed by a digit")?;
+                columns.extend(vec![0; 4 + run]);
+            }
+            'z' => strips.push(Vec::new()),
+            _ => columns.push(value(ch).filter(|column| *column < 32).ok_or("Unrecognised content in apgcode")?),
+        }
+    }
+    let width = strips.iter().map(|columns| columns.len()).max().unwrap_or(0);
+    let mut grid = vec![vec![false; width]; 5 * strips.len()];
+    for (index, columns) in strips.iter().enumerate() {
+        for (x, column) in columns.iter().enumerate() {
+            for y in 0..5 {
+                grid[5 * index + y][x] = column >> y & 1 == 1;
+            }
+        }
+    }
+    let generation = Generation::new(grid);
+    let (left, top, width, height) = generation.bounding_box().unwrap_or((0, 0, 0, 0));
+    Ok(generation.crop(left as isize, top as isize, width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rle::pattern;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("x = 2, y = 2\n2o$2o!", "xs4_33")]
+    #[case("x = 3, y = 3\nb2o$obo$bo!", "xs5_253")]
+    #[case("x = 4, y = 3\nb2o$o2bo$b2o!", "xs6_696")]
+    #[case("x = 4, y = 4\nb2o$o2bo$bobo$2bo!", "xs7_2596")]
+    #[case("x = 4, y = 4\nb2o$o2bo$o2bo$b2o!", "xs8_6996")]
+    #[case("x = 3, y = 1\n3o!", "xp2_7")]
+    #[case("x = 4, y = 4\n2o$2o$2b2o$2b2o!", "xp2_318c")]
+    #[case("x = 3, y = 3\nbo$2bo$3o!", "xq4_153")]
+    #[case("x = 5, y = 4\nbo2bo$o4b$o3bo$4o!", "xq4_6frc")]
+    #[case("x = 2, y = 1\n2o!", "xs0_0")]
+    fn test_encode(#[case] rle: &str, #[case] expected: &str) {
+        assert_eq!(Some(expected.to_owned()), encode(&pattern(rle), None, 100));
+    }
+
+    #[test]
+    fn test_encode_gives_up() {
+        assert_eq!(None, encode(&pattern("x = 3, y = 3\nb2o$2o$bo!"), None, 50));
+    }
+
+    #[rstest]
+    #[case(vec!["o....o"], "1y01")]
+    #[case(vec!["o...o"], "1x1")]
+    #[case(vec!["o.......................................o"], "1yz1")]
+    #[case(vec!["o........................................o"], "1yz01")]
+    #[case(vec!["o", ".", ".", ".", ".", "o"], "1z1")]
+    #[case(vec!["o.o", ".", ".", ".", ".", "..o"], "101zw1")]
+    fn test_wechsler(#[case] rows: Vec<&str>, #[case] expected: &str) {
+        let width = rows.iter().map(|row| row.len()).max().unwrap();
+        let grid: Grid = rows.iter()
+            .map(|row| (0..width).map(|x| row.as_bytes().get(x) == Some(&b'o')).collect())
+            .collect();
+        assert_eq!(expected, wechsler(&grid));
+    }
+
+    #[rstest]
+    #[case("xs4_33")]
+    #[case("xq4_153")]
+    #[case("xp2_318c")]
+    #[case("xs12_g8o653z11")]
+    #[case("xs14_1y01zw1")]
+    #[case("xs2_1yz1")]
+    #[case("xs3_1yzyz1z1")]
+    fn test_decode_round_trip(#[case] code: &str) {
+        let generation = decode(code).unwrap();
+        assert_eq!(code.split_once('_').unwrap().1, wechsler(&generation.grid));
+    }
+
+    #[test]
+    fn test_decode_empty() {
+        let generation = decode("xs0_").unwrap();
+        assert_eq!(None, generation.bounding_box());
+        assert_eq!(vec![vec![false; 3]; 2], generation.crop(-1, -1, 3, 2).grid);
+    }
+
+    #[rstest]
+    #[case("33", "An apgcode needs a prefix such as xs4_")]
+    #[case("xr4_33", "An apgcode must start with xs, xp or xq")]
+    #[case("xs_33", "An apgcode needs a population or period after its prefix")]
+    #[case("xs4_3!", "Unrecognised content in apgcode")]
+    #[case("xs4_3y", "An apgcode y must be followed by a digit")]
+    fn test_decode_err(#[case] code: &str, #[case] expected: &str) {
+        assert_eq!(Err(expected), decode(code));
+    }
+}
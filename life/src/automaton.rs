@@ -0,0 +1,43 @@
+use crate::generation::Generation;
+
+pub trait Automaton {
+    fn generation(&self) -> &Generation;
+
+    /// Generations run since the start or the last reset.
+    fn generation_count(&self) -> u64;
+
+    /// The rule being run, as a rulestring.
+    fn rule(&self) -> String;
+
+    fn step(&mut self);
+
+    /// Goes back a generation, if any earlier ones are remembered.
+    fn step_back(&mut self) -> bool {
+        false
+    }
+
+    /// How many generations `step_back` can still go back.
+    fn remembered(&self) -> u64 {
+        0
+    }
+
+    /// Runs forwards, or back through remembered generations, to `generation`.
+    fn goto(&mut self, generation: u64) -> Result<(), &'static str> {
+        if generation + self.remembered() < self.generation_count() {
+            return Err("That generation is no longer remembered");
+        }
+        while self.generation_count() > generation {
+            self.step_back();
+        }
+        while self.generation_count() < generation {
+            self.step();
+        }
+        Ok(())
+    }
+
+    /// Brings the cell at `(x, y)` to life or kills it, as when editing by hand.
+    fn set_cell(&mut self, x: usize, y: usize, alive: bool);
+
+    /// Starts again from `generation`, as when reseeding a soup.
+    fn reset(&mut self, generation: Generation);
+}
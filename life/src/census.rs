@@ -0,0 +1,205 @@
+use crate::apgcode;
+use crate::automaton::Automaton;
+use crate::game::Game;
+use crate::generation::Generation;
+use crate::objects;
+use crate::period::PeriodDetector;
+use crate::rule::Rule;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::str::FromStr;
+
+// Empty space left around each soup for it to spread into.
+const FIELD_MARGIN: usize = 48;
+
+// Objects this close to the edge of the field are what's left of debris
+// which hit it, so aren't counted.
+const EDGE: usize = 2;
+
+/// How a census is written out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            _ => Err("Format must be csv or json"),
+        }
+    }
+}
+
+/// A tally of the objects found in the ash of many soups, by apgcode, with
+/// the seeds of the soups each was found in.
+#[derive(Debug, Default)]
+pub struct Census {
+    pub soups: u64,
+    pub unsettled: Vec<u64>,
+    counts: HashMap<String, u64>,
+    seeds: HashMap<String, Vec<u64>>,
+}
+
+impl Census {
+    pub fn new() -> Census {
+        Census::default()
+    }
+
+    /// Counts the objects left by the soup with `seed`, or notes that it
+    /// never settled.
+    pub fn add(&mut self, seed: u64, ash: Option<Vec<String>>) {
+        self.soups += 1;
+        let Some(ash) = ash else {
+            self.unsettled.push(seed);
+            return;
+        };
+        for code in ash {
+            *self.counts.entry(code.clone()).or_insert(0) += 1;
+            let seeds = self.seeds.entry(code).or_default();
+            if seeds.last() != Some(&seed) {
+                seeds.push(seed);
+            }
+        }
+    }
+
+    /// Every apgcode found with its count, the most common first.
+    pub fn counts(&self) -> Vec<(&str, u64)> {
+        let mut counts: Vec<(&str, u64)> = self.counts.iter().map(|(code, count)| (code.as_str(), *count)).collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        counts
+    }
+
+    /// The objects found no more than `threshold` times, with the seeds of
+    /// the soups they came from.
+    pub fn rare(&self, threshold: u64) -> Vec<(&str, &[u64])> {
+        self.counts().into_iter()
+            .filter(|(_, count)| *count <= threshold)
+            .map(|(code, _)| (code, self.seeds[code].as_slice()))
+            .collect()
+    }
+
+    /// Writes the census as CSV or JSON, giving the soup seeds of objects
+    /// found no more than `rare` times.
+    pub fn write(&self, format: Format, rare: u64) -> String {
+        let seeds = |code: &str, count: u64| match count <= rare {
+            true => self.seeds[code].clone(),
+            false => Vec::new(),
+        };
+        let mut out = String::new();
+        match format {
+            Format::Csv => {
+                out.push_str("apgcode,count,seeds\n");
+                for (code, count) in self.counts() {
+                    let seeds: Vec<String> = seeds(code, count).iter().map(|seed| seed.to_string()).collect();
+                    writeln!(out, "{},{},{}", code, count, seeds.join(" ")).unwrap();
+                }
+            }
+            Format::Json => {
+                let list = |seeds: &[u64]| seeds.iter().map(|seed| seed.to_string()).collect::<Vec<_>>().join(", ");
+                writeln!(out, "{{\n  \"soups\": {},\n  \"unsettled\": [{}],\n  \"census\": [", self.soups, list(&self.unsettled)).unwrap();
+                let counts = self.counts();
+                for (index, (code, count)) in counts.iter().enumerate() {
+                    write!(out, "    {{\"apgcode\": \"{}\", \"count\": {}", code, count).unwrap();
+                    let seeds = seeds(code, *count);
+                    if !seeds.is_empty() {
+                        write!(out, ", \"seeds\": [{}]", list(&seeds)).unwrap();
+                    }
+                    out.push_str(if index + 1 < counts.len() { "},\n" } else { "}\n" });
+                }
+                out.push_str("  ]\n}\n");
+            }
+        }
+        out
+    }
+}
+
+/// Runs `soup` in the middle of an empty field until it settles, then names
+/// each object left with its apgcode, or `unknown` if it doesn't repeat on
+/// its own. Gives `None` if it hasn't settled within `max_generations`.
+pub fn ash(soup: &Generation, rule: &Rule, max_generations: u64) -> Option<Vec<String>> {
+    let width = soup.grid.first().map_or(0, |row| row.len()) + 2 * FIELD_MARGIN;
+    let height = soup.grid.len() + 2 * FIELD_MARGIN;
+    let field = soup.crop(-(FIELD_MARGIN as isize), -(FIELD_MARGIN as isize), width, height);
+    let mut game = Game::new(field, Some(rule.clone()));
+    let mut detector = PeriodDetector::new();
+    let mut cycle = detector.observe(0, game.generation());
+    while cycle.is_none() && game.generation_count() < max_generations {
+        game.step();
+        cycle = detector.observe(game.generation_count(), game.generation());
+    }
+    let period = cycle?.period;
+    let ash = objects::separate(game.generation(), Some(rule.clone()), period).into_iter()
+        .filter(|object| {
+            let (object_width, object_height) = (object.generation.grid[0].len(), object.generation.grid.len());
+            object.x >= EDGE && object.y >= EDGE && object.x + object_width + EDGE <= width && object.y + object_height + EDGE <= height
+        })
+        .map(|object| apgcode::encode(&object.generation, Some(rule.clone()), max_generations).unwrap_or_else(|| "unknown".to_owned()))
+        .collect();
+    Some(ash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rle;
+    use rstest::rstest;
+
+    fn census() -> Census {
+        let mut census = Census::new();
+        census.add(7, Some(vec!["xs4_33".to_owned(), "xp2_7".to_owned(), "xs4_33".to_owned()]));
+        census.add(8, None);
+        census.add(9, Some(vec!["xs4_33".to_owned()]));
+        census
+    }
+
+    #[test]
+    fn test_census_counts() {
+        let census = census();
+        assert_eq!(3, census.soups);
+        assert_eq!(vec![8], census.unsettled);
+        assert_eq!(vec![("xs4_33", 3), ("xp2_7", 1)], census.counts());
+        assert_eq!(vec![("xp2_7", &[7][..])], census.rare(1));
+        assert_eq!(vec![("xs4_33", &[7, 9][..]), ("xp2_7", &[7][..])], census.rare(3));
+    }
+
+    #[test]
+    fn test_census_csv() {
+        assert_eq!("apgcode,count,seeds\nxs4_33,3,\nxp2_7,1,7\n", census().write(Format::Csv, 1));
+    }
+
+    #[test]
+    fn test_census_json() {
+        assert_eq!(
+            "{\n  \"soups\": 3,\n  \"unsettled\": [8],\n  \"census\": [\n    {\"apgcode\": \"xs4_33\", \"count\": 3, \"seeds\": [7, 9]},\n    {\"apgcode\": \"xp2_7\", \"count\": 1, \"seeds\": [7]}\n  ]\n}\n",
+            census().write(Format::Json, 3),
+        );
+    }
+
+    #[rstest]
+    #[case("csv", Ok(Format::Csv))]
+    #[case("json", Ok(Format::Json))]
+    #[case("xml", Err("Format must be csv or json"))]
+    fn test_format_from_str(#[case] s: &str, #[case] expected: Result<Format, &'static str>) {
+        assert_eq!(expected, s.parse());
+    }
+
+    #[test]
+    fn test_ash() {
+        // A pre-block beside a blinker far enough away not to touch it.
+        let soup = rle::pattern("x = 8, y = 2\n2o3b3o$o!");
+        let mut ash = ash(&soup, &Rule::default(), 100).unwrap();
+        ash.sort();
+        assert_eq!(vec!["xp2_7".to_owned(), "xs4_33".to_owned()], ash);
+    }
+
+    #[test]
+    fn test_ash_unsettled() {
+        let soup = rle::pattern("x = 3, y = 3\nb2o$2o$bo!");
+        assert_eq!(None, ash(&soup, &Rule::default(), 20));
+    }
+}
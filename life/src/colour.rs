@@ -0,0 +1,191 @@
+use crate::generation::Generation;
+use crate::render::Rgb;
+use crossterm::style::Color;
+use std::str::FromStr;
+
+/// How long each cell has been alive or dead: `n > 0` for a cell alive for
+/// `n` generations (so 1 is newborn), `-n` for one dead for `n` generations
+/// and 0 for one that has never lived.
+#[derive(Debug, PartialEq)]
+pub struct Ages {
+    pub ages: Vec<Vec<i32>>,
+}
+
+impl Ages {
+    pub fn new(generation: &Generation) -> Ages {
+        let ages = generation.grid.iter()
+            .map(|row| row.iter().map(|alive| *alive as i32).collect())
+            .collect();
+        Ages{ages}
+    }
+
+    /// Ages every cell by one generation, to match `generation`.
+    pub fn update(&mut self, generation: &Generation) {
+        for (ages, cells) in self.ages.iter_mut().zip(&generation.grid) {
+            for (age, alive) in ages.iter_mut().zip(cells) {
+                *age = match (*alive, *age) {
+                    (true, age) if age > 0 => age.saturating_add(1),
+                    (true, _) => 1,
+                    (false, age) if age > 0 => -1,
+                    (false, age) if age < 0 => age.saturating_sub(1),
+                    (false, _) => 0,
+                };
+            }
+        }
+    }
+
+    /// Folds together the ages of cells drawn as one pixel: the youngest live
+    /// cell if there are any, otherwise the most recent death.
+    pub fn merge(a: i32, b: i32) -> i32 {
+        match (a > 0, b > 0) {
+            (true, true) => a.min(b),
+            (true, false) => a,
+            (false, true) => b,
+            _ if a == 0 => b,
+            _ if b == 0 => a,
+            _ => a.max(b),
+        }
+    }
+}
+
+/// What the colours show: how long cells have lived, whether they were just
+/// born, survived or just died, or a fading trail behind dead cells.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Scheme {
+    Age,
+    Status,
+    Trail,
+}
+
+impl FromStr for Scheme {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "age" => Ok(Scheme::Age),
+            "status" => Ok(Scheme::Status),
+            "trail" => Ok(Scheme::Trail),
+            _ => Err("Colour must be one of age, status or trail"),
+        }
+    }
+}
+
+/// Whether the terminal takes 24-bit colours or only the 256-colour palette.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Depth {
+    Ansi256,
+    TrueColour,
+}
+
+impl FromStr for Depth {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "256" => Ok(Depth::Ansi256),
+            "truecolor" | "truecolour" => Ok(Depth::TrueColour),
+            _ => Err("Colour depth must be 256 or truecolor"),
+        }
+    }
+}
+
+const NEWBORN: Rgb = (255, 255, 160);
+const ANCIENT: Rgb = (40, 80, 255);
+const BORN: Rgb = (80, 255, 80);
+const SURVIVED: Rgb = (230, 230, 230);
+const DIED: Rgb = (255, 60, 60);
+
+// Ages are shown on a log scale, reaching the oldest colour at this age.
+const OLD_AGE: f64 = 1000.0;
+
+fn blend(from: Rgb, to: Rgb, t: f64) -> Rgb {
+    let channel = |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * t).round() as u8;
+    (channel(from.0, to.0), channel(from.1, to.1), channel(from.2, to.2))
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Palette {
+    pub scheme: Scheme,
+    pub depth: Depth,
+    pub trail: u32,
+}
+
+impl Palette {
+    /// The colour of a cell of the given age, or `None` to leave it blank.
+    pub fn colour(&self, age: i32) -> Option<Rgb> {
+        match self.scheme {
+            Scheme::Age if age > 0 => Some(blend(NEWBORN, ANCIENT, ((age as f64).ln() / OLD_AGE.ln()).min(1.0))),
+            Scheme::Status if age == 1 => Some(BORN),
+            Scheme::Status if age > 1 => Some(SURVIVED),
+            Scheme::Status if age == -1 => Some(DIED),
+            Scheme::Trail if age > 0 => Some(SURVIVED),
+            Scheme::Trail if age < 0 && age.unsigned_abs() <= self.trail => {
+                let fade = age.unsigned_abs() as f64 / (self.trail + 1) as f64;
+                Some(blend(DIED, (0, 0, 0), fade))
+            }
+            _ => None,
+        }
+    }
+
+    /// The closest colour the terminal can show.
+    pub fn terminal_colour(&self, (r, g, b): Rgb) -> Color {
+        match self.depth {
+            Depth::TrueColour => Color::Rgb{r, g, b},
+            Depth::Ansi256 => {
+                // The 6x6x6 colour cube starts at 16.
+                let level = |channel: u8| ((channel as u16 * 5 + 127) / 255) as u8;
+                Color::AnsiValue(16 + 36 * level(r) + 6 * level(g) + level(b))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_ages_update() {
+        let mut ages = Ages::new(&Generation::new(vec![vec![true, true, false]]));
+        ages.update(&Generation::new(vec![vec![true, false, true]]));
+        assert_eq!(vec![vec![2, -1, 1]], ages.ages);
+        ages.update(&Generation::new(vec![vec![false, false, true]]));
+        assert_eq!(vec![vec![-1, -2, 2]], ages.ages);
+    }
+
+    #[rstest]
+    #[case(3, 5, 3)]
+    #[case(-1, 4, 4)]
+    #[case(0, -2, -2)]
+    #[case(-3, -1, -1)]
+    #[case(0, 0, 0)]
+    fn test_ages_merge(#[case] a: i32, #[case] b: i32, #[case] expected: i32) {
+        assert_eq!(expected, Ages::merge(a, b));
+    }
+
+    #[rstest]
+    #[case(Scheme::Age, 1, Some(NEWBORN))]
+    #[case(Scheme::Age, 5000, Some(ANCIENT))]
+    #[case(Scheme::Age, -1, None)]
+    #[case(Scheme::Status, 1, Some(BORN))]
+    #[case(Scheme::Status, 7, Some(SURVIVED))]
+    #[case(Scheme::Status, -1, Some(DIED))]
+    #[case(Scheme::Status, -2, None)]
+    #[case(Scheme::Trail, -3, Some((64, 15, 15)))]
+    #[case(Scheme::Trail, -4, None)]
+    #[case(Scheme::Trail, 0, None)]
+    fn test_palette_colour(#[case] scheme: Scheme, #[case] age: i32, #[case] expected: Option<Rgb>) {
+        let palette = Palette{scheme, depth: Depth::TrueColour, trail: 3};
+        assert_eq!(expected, palette.colour(age));
+    }
+
+    #[rstest]
+    #[case(Depth::TrueColour, (255, 60, 60), Color::Rgb{r: 255, g: 60, b: 60})]
+    #[case(Depth::Ansi256, (255, 60, 60), Color::AnsiValue(203))]
+    #[case(Depth::Ansi256, (0, 0, 0), Color::AnsiValue(16))]
+    fn test_palette_terminal_colour(#[case] depth: Depth, #[case] rgb: Rgb, #[case] expected: Color) {
+        let palette = Palette{scheme: Scheme::Age, depth, trail: 8};
+        assert_eq!(expected, palette.terminal_colour(rgb));
+    }
+}
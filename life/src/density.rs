@@ -0,0 +1,192 @@
+use std::str::FromStr;
+
+/// How likely each cell of a soup is to start alive, either the same
+/// everywhere (`0.25`), a gradient between two densities (`horizontal:0.1:0.5`,
+/// `vertical:...` or `radial:...` from the centre outwards) or a checkerboard
+/// of two densities in `size` squares (`checker:0.1:0.6:8`).
+#[derive(Debug, PartialEq)]
+pub enum DensityMap {
+    Uniform(f64),
+    Horizontal(f64, f64),
+    Vertical(f64, f64),
+    Radial(f64, f64),
+    Checker(f64, f64, usize),
+}
+
+fn parse_density(s: &str) -> Result<f64, &'static str> {
+    match s.parse::<f64>() {
+        Ok(density) if (0.0..=1.0).contains(&density) => Ok(density),
+        Ok(_) => Err("Density must be between 0 and 1"),
+        Err(_) => Err("Unrecognised density map"),
+    }
+}
+
+impl FromStr for DensityMap {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        match parts[..] {
+            [density] => Ok(DensityMap::Uniform(parse_density(density)?)),
+            ["horizontal", from, to] => Ok(DensityMap::Horizontal(parse_density(from)?, parse_density(to)?)),
+            ["vertical", from, to] => Ok(DensityMap::Vertical(parse_density(from)?, parse_density(to)?)),
+            ["radial", from, to] => Ok(DensityMap::Radial(parse_density(from)?, parse_density(to)?)),
+            ["checker", even, odd, size] => {
+                let size = match size.parse() {
+                    Ok(size) if size > 0 => size,
+                    _ => { return Err("Checker squares must be at least one cell"); }
+                };
+                Ok(DensityMap::Checker(parse_density(even)?, parse_density(odd)?, size))
+            }
+            _ => Err("Unrecognised density map"),
+        }
+    }
+}
+
+fn fraction(position: usize, length: usize) -> f64 {
+    if length < 2 {
+        return 0.0;
+    }
+    position as f64 / (length - 1) as f64
+}
+
+impl DensityMap {
+    pub fn at(&self, x: usize, y: usize, width: usize, height: usize) -> f64 {
+        let blend = |from: f64, to: f64, t: f64| from + (to - from) * t;
+        match self {
+            Self::Uniform(density) => *density,
+            Self::Horizontal(from, to) => blend(*from, *to, fraction(x, width)),
+            Self::Vertical(from, to) => blend(*from, *to, fraction(y, height)),
+            Self::Radial(from, to) => {
+                let dx = x as f64 - (width as f64 - 1.0) / 2.0;
+                let dy = y as f64 - (height as f64 - 1.0) / 2.0;
+                let corner = ((width as f64 - 1.0).powi(2) + (height as f64 - 1.0).powi(2)).sqrt() / 2.0;
+                let t = if corner > 0.0 { (dx * dx + dy * dy).sqrt() / corner } else { 0.0 };
+                blend(*from, *to, t)
+            }
+            Self::Checker(even, odd, size) => {
+                if (x / size + y / size).is_multiple_of(2) { *even } else { *odd }
+            }
+        }
+    }
+
+    pub fn mean(&self, width: usize, height: usize) -> f64 {
+        let mut total = 0.0;
+        for y in 0..height {
+            for x in 0..width {
+                total += self.at(x, y, width, height);
+            }
+        }
+        total / (width * height) as f64
+    }
+}
+
+/// Whether a soup has exactly as many cells as its densities add up to,
+/// chosen without replacement, or gives each cell its own independent chance.
+#[derive(Debug, PartialEq)]
+pub enum Fill {
+    Exact,
+    Bernoulli,
+}
+
+impl FromStr for Fill {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "exact" => Ok(Fill::Exact),
+            "bernoulli" => Ok(Fill::Bernoulli),
+            _ => Err("Fill must be exact or bernoulli"),
+        }
+    }
+}
+
+impl Fill {
+    /// Chooses which cells come alive given the density at each of them.
+    pub fn choose(&self, rng: &fastrand::Rng, densities: &[f64]) -> Vec<bool> {
+        match self {
+            Self::Bernoulli => densities.iter().map(|density| rng.f64() < *density).collect(),
+            Self::Exact => {
+                // Weighted sampling without replacement: giving each cell the
+                // key u^(1/density) and taking the largest keys picks cells in
+                // proportion to their density, and never the same cell twice.
+                let amount = densities.iter().sum::<f64>().round() as usize;
+                let keys: Vec<f64> = densities.iter().map(|density| {
+                    if *density > 0.0 { rng.f64().powf(1.0 / density) } else { -1.0 }
+                }).collect();
+                let mut order: Vec<usize> = (0..densities.len()).collect();
+                order.sort_by(|a, b| keys[*b].total_cmp(&keys[*a]));
+                let mut chosen = vec![false; densities.len()];
+                for index in order.into_iter().take(amount) {
+                    chosen[index] = true;
+                }
+                chosen
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("0.25", DensityMap::Uniform(0.25))]
+    #[case("horizontal:0.1:0.5", DensityMap::Horizontal(0.1, 0.5))]
+    #[case("vertical:1:0", DensityMap::Vertical(1.0, 0.0))]
+    #[case("radial:0.5:0", DensityMap::Radial(0.5, 0.0))]
+    #[case("checker:0.1:0.6:8", DensityMap::Checker(0.1, 0.6, 8))]
+    fn test_density_map_from_str_ok(#[case] map: &str, #[case] expected: DensityMap) {
+        assert_eq!(Ok(expected), map.parse());
+    }
+
+    #[rstest]
+    #[case("1.5", "Density must be between 0 and 1")]
+    #[case("horizontal:0.1:-0.5", "Density must be between 0 and 1")]
+    #[case("diagonal:0.1:0.5", "Unrecognised density map")]
+    #[case("checker:0.1:0.6:0", "Checker squares must be at least one cell")]
+    fn test_density_map_from_str_err(#[case] map: &str, #[case] expected: &str) {
+        assert_eq!(Err(expected), map.parse::<DensityMap>());
+    }
+
+    #[rstest]
+    #[case(DensityMap::Horizontal(0.0, 1.0), (0, 2), 0.0)]
+    #[case(DensityMap::Horizontal(0.0, 1.0), (4, 2), 1.0)]
+    #[case(DensityMap::Vertical(0.2, 0.6), (0, 2), 0.4)]
+    #[case(DensityMap::Radial(1.0, 0.0), (2, 2), 1.0)]
+    #[case(DensityMap::Radial(1.0, 0.0), (4, 4), 0.0)]
+    #[case(DensityMap::Checker(0.1, 0.9, 2), (1, 1), 0.1)]
+    #[case(DensityMap::Checker(0.1, 0.9, 2), (2, 1), 0.9)]
+    fn test_density_map_at(#[case] map: DensityMap, #[case] cell: (usize, usize), #[case] expected: f64) {
+        assert!((expected - map.at(cell.0, cell.1, 5, 5)).abs() < 1e-9);
+    }
+
+    #[rstest]
+    #[case(0.25, 100, 25)]
+    #[case(0.5, 7, 4)]
+    #[case(1.0, 64, 64)]
+    #[case(0.0, 64, 0)]
+    fn test_fill_exact_amount(#[case] density: f64, #[case] cells: usize, #[case] expected: usize) {
+        let rng = fastrand::Rng::with_seed(1);
+        let chosen = Fill::Exact.choose(&rng, &vec![density; cells]);
+        assert_eq!(expected, chosen.iter().filter(|cell| **cell).count());
+    }
+
+    #[test]
+    fn test_fill_exact_follows_density() {
+        let rng = fastrand::Rng::with_seed(1);
+        let mut densities = vec![0.0; 50];
+        densities.extend(vec![0.5; 50]);
+        let chosen = Fill::Exact.choose(&rng, &densities);
+        assert!(chosen[..50].iter().all(|cell| !cell));
+        assert_eq!(25, chosen[50..].iter().filter(|cell| **cell).count());
+    }
+
+    #[test]
+    fn test_fill_bernoulli_is_seeded() {
+        let choose = |seed| Fill::Bernoulli.choose(&fastrand::Rng::with_seed(seed), &[0.5; 64]);
+        assert_eq!(choose(1), choose(1));
+        assert_ne!(choose(1), choose(2));
+    }
+}
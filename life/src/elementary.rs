@@ -0,0 +1,213 @@
+use crate::automaton::Automaton;
+use crate::generation::Generation;
+use std::fmt;
+use std::str::FromStr;
+
+/// A one-dimensional rule, either one of Wolfram's 256 elementary rules
+/// (`W30`, `W110`) or a totalistic rule over a radius `r` window (`T20R2`),
+/// where bit `n` of the code gives the next state for a window sum of `n`.
+#[derive(Debug, PartialEq)]
+pub enum ElementaryRule {
+    Wolfram(u8),
+    Totalistic{code: u64, radius: usize},
+}
+
+impl FromStr for ElementaryRule {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(code) = s.strip_prefix('W') {
+            let code = code.parse().map_err(|_| "Wolfram rule must be a number from 0 to 255")?;
+            return Ok(ElementaryRule::Wolfram(code));
+        }
+        if let Some(rest) = s.strip_prefix('T') {
+            let (code, radius) = rest.split_once('R').unwrap_or((rest, "1"));
+            let code: u64 = code.parse().map_err(|_| "Unrecognised totalistic code")?;
+            let radius: usize = radius.parse().map_err(|_| "Unrecognised radius")?;
+            if radius == 0 || radius > 31 {
+                return Err("Radius must be from 1 to 31");
+            }
+            let window = 2 * radius as u32 + 1;
+            if window < 63 && code >> (window + 1) != 0 {
+                return Err("Totalistic code is too large for the radius");
+            }
+            return Ok(ElementaryRule::Totalistic{code, radius});
+        }
+        Err("Unrecognised content in rulestring")
+    }
+}
+
+impl fmt::Display for ElementaryRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Wolfram(code) => write!(f, "W{}", code),
+            Self::Totalistic{code, radius: 1} => write!(f, "T{}", code),
+            Self::Totalistic{code, radius} => write!(f, "T{}R{}", code, radius),
+        }
+    }
+}
+
+impl ElementaryRule {
+    pub fn radius(&self) -> usize {
+        match self {
+            Self::Wolfram(_) => 1,
+            Self::Totalistic{radius, ..} => *radius,
+        }
+    }
+
+    /// Next state of the centre cell of `window`, which spans `2*radius+1` cells.
+    pub fn apply(&self, window: &[bool]) -> bool {
+        match self {
+            Self::Wolfram(code) => {
+                let index = window.iter().fold(0, |acc, cell| acc << 1 | *cell as u8);
+                code >> index & 1 == 1
+            }
+            Self::Totalistic{code, ..} => {
+                let sum = window.iter().filter(|cell| **cell).count();
+                code >> sum & 1 == 1
+            }
+        }
+    }
+
+    pub fn next_row(&self, row: &[bool]) -> Vec<bool> {
+        let radius = self.radius();
+        let mut padded = vec![false; radius];
+        padded.extend_from_slice(row);
+        padded.extend(vec![false; radius]);
+        padded.windows(2 * radius + 1)
+            .map(|window| self.apply(window))
+            .collect()
+    }
+}
+
+/// Runs a 1D rule, drawing each generation as a new row below the last so
+/// the grid shows a space-time diagram which scrolls once it is full.
+pub struct Elementary {
+    rule: ElementaryRule,
+    row: Vec<bool>,
+    diagram: Generation,
+    filled: usize,
+    generation_count: u64,
+}
+
+impl Elementary {
+    pub fn new(rule: ElementaryRule, row: Vec<bool>, height: usize) -> Elementary {
+        let mut grid = vec![vec![false; row.len()]; height];
+        grid[0] = row.clone();
+        Elementary{rule, row, diagram: Generation::new(grid), filled: 1, generation_count: 0}
+    }
+
+    pub fn single(width: usize) -> Vec<bool> {
+        let mut row = vec![false; width];
+        row[width / 2] = true;
+        row
+    }
+
+    pub fn soup(rng: &fastrand::Rng, width: usize, density: f64) -> Vec<bool> {
+        (0..width).map(|_| rng.f64() < density).collect()
+    }
+}
+
+impl Automaton for Elementary {
+    fn generation(&self) -> &Generation {
+        &self.diagram
+    }
+
+    fn generation_count(&self) -> u64 {
+        self.generation_count
+    }
+
+    fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    fn step(&mut self) {
+        self.generation_count += 1;
+        self.row = self.rule.next_row(&self.row);
+        let grid = &mut self.diagram.grid;
+        if self.filled < grid.len() {
+            grid[self.filled] = self.row.clone();
+            self.filled += 1;
+        } else {
+            grid.remove(0);
+            grid.push(self.row.clone());
+        }
+    }
+
+    /// Edits the diagram. Only edits to the newest row affect what follows.
+    fn set_cell(&mut self, x: usize, y: usize, alive: bool) {
+        self.diagram.grid[y][x] = alive;
+        if y + 1 == self.filled {
+            self.row[x] = alive;
+        }
+    }
+
+    /// Restarts the diagram from the first row of `generation`.
+    fn reset(&mut self, generation: Generation) {
+        let mut grid = vec![vec![false; self.row.len()]; self.diagram.grid.len()];
+        grid[0] = generation.grid[0].clone();
+        self.row = grid[0].clone();
+        self.diagram = Generation::new(grid);
+        self.filled = 1;
+        self.generation_count = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn row(cells: &str) -> Vec<bool> {
+        cells.chars().map(|ch| ch == 'o').collect()
+    }
+
+    #[rstest]
+    #[case("W30", ElementaryRule::Wolfram(30))]
+    #[case("W110", ElementaryRule::Wolfram(110))]
+    #[case("T10", ElementaryRule::Totalistic{code: 10, radius: 1})]
+    #[case("T20R2", ElementaryRule::Totalistic{code: 20, radius: 2})]
+    fn test_elementary_rule_from_str_ok(#[case] rulestring: &str, #[case] expected: ElementaryRule) {
+        assert_eq!(Ok(expected), rulestring.parse());
+    }
+
+    #[rstest]
+    #[case("W256", "Wolfram rule must be a number from 0 to 255")]
+    #[case("T16", "Totalistic code is too large for the radius")]
+    #[case("T1R0", "Radius must be from 1 to 31")]
+    #[case("B3/S23", "Unrecognised content in rulestring")]
+    fn test_elementary_rule_from_str_err(#[case] rulestring: &str, #[case] expected: &str) {
+        assert_eq!(Err(expected), rulestring.parse::<ElementaryRule>());
+    }
+
+    #[rstest]
+    #[case("W30")]
+    #[case("T10")]
+    #[case("T14R2")]
+    fn test_elementary_rule_display(#[case] rulestring: &str) {
+        assert_eq!(rulestring, rulestring.parse::<ElementaryRule>().unwrap().to_string());
+    }
+
+    #[rstest]
+    #[case("W30", "...o...", "..ooo..")]
+    #[case("W30", "..ooo..", ".oo..o.")]
+    #[case("W90", "..o.o..", ".o...o.")]
+    #[case("W110", "...o...", "..oo...")]
+    #[case("T14R2", "...o...", ".ooooo.")] // 14 = sums 1, 2 and 3
+    #[case("T14R2", ".ooooo.", "oo...oo")]
+    #[case("T20R2", "..ooo..", ".o...o.")] // 20 = sums 2 and 4
+    fn test_elementary_rule_next_row(#[case] rule: ElementaryRule, #[case] current: &str, #[case] expected: &str) {
+        assert_eq!(row(expected), rule.next_row(&row(current)));
+    }
+
+    #[test]
+    fn test_elementary_diagram_scrolls() {
+        let mut automaton = Elementary::new(ElementaryRule::Wolfram(30), row("...o..."), 2);
+        automaton.step();
+        assert_eq!(vec![row("...o..."), row("..ooo..")], automaton.generation().grid);
+        automaton.step();
+        assert_eq!(vec![row("..ooo.."), row(".oo..o.")], automaton.generation().grid);
+        automaton.reset(Generation::new(vec![row("..o.o..")]));
+        assert_eq!(vec![row("..o.o.."), row(".......")], automaton.generation().grid);
+    }
+}
@@ -0,0 +1,282 @@
+use crate::analysis::{self, Class};
+use crate::apgcode;
+use crate::automaton::Automaton;
+use crate::generation::Generation;
+use crate::objects::{self, Cells};
+use crate::rle;
+use crate::rule::Rule;
+use std::collections::HashMap;
+use std::fmt;
+
+type Grid = Vec<Vec<bool>>;
+
+const SHIPS: &[&str] = &[
+    include_str!("../data/glider.rle"),
+    include_str!("../data/lwss.rle"),
+    include_str!("../data/mwss.rle"),
+    include_str!("../data/hwss.rle"),
+];
+
+// Ships are looked for among the cells this close to the edge of the grid.
+const MARGIN: usize = 12;
+
+// A ship counts as escaping once it is this close to the edge it's heading for.
+const BAND: usize = 4;
+
+// Live cells closer together than this belong to the same cluster, so a
+// cluster matching a ship has nothing near enough to disturb it.
+const REACH: i32 = 2;
+
+/// A spaceship seen leaving the grid, in the direction it moves in and its
+/// phase, counted in generations from the shape stored for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Escape {
+    pub name: &'static str,
+    pub direction: (isize, isize),
+    pub phase: u64,
+    pub x: usize,
+    pub y: usize,
+    pub generation: u64,
+}
+
+/// Spots spaceships heading off the edge of the grid, and can remove them
+/// there so that they don't crash into it. Each is counted once, as it comes
+/// within a few cells of the edge.
+pub struct EscapeDetector {
+    ships: HashMap<Grid, (&'static str, (isize, isize), u64)>,
+    delete: bool,
+    tracked: Vec<Escape>,
+    escapes: Vec<Escape>,
+}
+
+fn signum((dx, dy): (isize, isize)) -> (isize, isize) {
+    (dx.signum(), dy.signum())
+}
+
+impl EscapeDetector {
+    /// Learns every phase and orientation of the glider and the standard
+    /// spaceships which are spaceships under `rule`.
+    pub fn new(rule: &Rule, delete: bool) -> EscapeDetector {
+        let mut ships = HashMap::new();
+        for contents in SHIPS {
+            let name = rle::name(contents).unwrap();
+            let (grid, _) = rle::parse_file(contents).unwrap();
+            for orientation in apgcode::orientations(&grid) {
+                let generation = Generation::new(orientation);
+                let analysis = analysis::analyze(generation.grid.clone(), Some(rule.clone()), 10);
+                let (Class::Spaceship, Some(cycle)) = (analysis.class, analysis.cycle) else {
+                    continue;
+                };
+                if cycle.start != 0 {
+                    continue;
+                }
+                for (phase, shape) in apgcode::phases(&generation, Some(rule.clone()), &cycle).into_iter().enumerate() {
+                    let (left, top, width, height) = shape.bounding_box().unwrap();
+                    let shape = shape.crop(left as isize, top as isize, width, height).grid;
+                    ships.entry(shape).or_insert((name, signum(cycle.displacement), phase as u64));
+                }
+            }
+        }
+        EscapeDetector{ships, delete, tracked: Vec::new(), escapes: Vec::new()}
+    }
+
+    /// Every spaceship counted so far.
+    pub fn escapes(&self) -> &[Escape] {
+        &self.escapes
+    }
+
+    /// Looks for spaceships about to leave the automaton's grid, counting
+    /// those not already seen the generation before and removing them all
+    /// if asked to.
+    pub fn observe(&mut self, automaton: &mut dyn Automaton) {
+        let generation = automaton.generation();
+        let height = generation.grid.len();
+        let width = generation.grid.first().map_or(0, |row| row.len());
+        let inside = |x: usize, y: usize| x.min(y).min(width - 1 - x).min(height - 1 - y) >= MARGIN;
+        let near_edge: Grid = generation.grid.iter().enumerate()
+            .map(|(y, row)| row.iter().enumerate().map(|(x, alive)| *alive && !inside(x, y)).collect())
+            .collect();
+        let reach: Vec<(i32, i32)> = (-REACH..=REACH)
+            .flat_map(|dy| (-REACH..=REACH).map(move |dx| (dx, dy)))
+            .filter(|offset| *offset != (0, 0))
+            .collect();
+        let mut found: Vec<(Escape, Cells)> = Vec::new();
+        for cluster in objects::components(&near_edge, &reach) {
+            // Clusters cut off by the margin may only look like ships.
+            if cluster.iter().any(|(x, y)| x.min(y).min(&(width - 1 - x)).min(&(height - 1 - y)) + REACH as usize >= MARGIN) {
+                continue;
+            }
+            let left = cluster.iter().map(|(x, _)| *x).min().unwrap();
+            let top = cluster.iter().map(|(_, y)| *y).min().unwrap();
+            let right = cluster.iter().map(|(x, _)| *x).max().unwrap();
+            let bottom = cluster.iter().map(|(_, y)| *y).max().unwrap();
+            let mut shape = vec![vec![false; right - left + 1]; bottom - top + 1];
+            for (x, y) in &cluster {
+                shape[y - top][x - left] = true;
+            }
+            let Some((name, direction, phase)) = self.ships.get(&shape) else {
+                continue;
+            };
+            let leaving = (direction.0 < 0 && left < BAND) || (direction.0 > 0 && right + BAND >= width)
+                || (direction.1 < 0 && top < BAND) || (direction.1 > 0 && bottom + BAND >= height);
+            if leaving {
+                let escape = Escape{
+                    name,
+                    direction: *direction,
+                    phase: *phase,
+                    x: left,
+                    y: top,
+                    generation: automaton.generation_count(),
+                };
+                found.push((escape, cluster));
+            }
+        }
+        let tracked = std::mem::take(&mut self.tracked);
+        for (escape, cluster) in found {
+            let seen = tracked.iter().any(|other| {
+                other.name == escape.name && other.direction == escape.direction
+                    && other.x.abs_diff(escape.x) <= 2 && other.y.abs_diff(escape.y) <= 2
+            });
+            if !seen {
+                self.escapes.push(escape.clone());
+            }
+            if self.delete {
+                for (x, y) in cluster {
+                    automaton.set_cell(x, y, false);
+                }
+            } else {
+                self.tracked.push(escape);
+            }
+        }
+    }
+
+    /// How many spaceships of each kind, heading which way in which phase,
+    /// have escaped, and how often.
+    pub fn streams(&self) -> Vec<Stream> {
+        let mut streams: Vec<Stream> = Vec::new();
+        for escape in &self.escapes {
+            let same = |stream: &&mut Stream| {
+                (stream.name, stream.direction, stream.phase) == (escape.name, escape.direction, escape.phase)
+            };
+            match streams.iter_mut().find(same) {
+                Some(stream) => {
+                    stream.count += 1;
+                    stream.last = escape.generation;
+                }
+                None => streams.push(Stream{
+                    name: escape.name,
+                    direction: escape.direction,
+                    phase: escape.phase,
+                    count: 1,
+                    first: escape.generation,
+                    last: escape.generation,
+                }),
+            }
+        }
+        streams
+    }
+}
+
+/// Spaceships of one kind escaping the same way, in the same phase.
+#[derive(Debug, PartialEq)]
+pub struct Stream {
+    pub name: &'static str,
+    pub direction: (isize, isize),
+    pub phase: u64,
+    pub count: u64,
+    pub first: u64,
+    pub last: u64,
+}
+
+impl fmt::Display for Stream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}{} moving ({}, {}) in phase {}",
+            self.count,
+            self.name.to_lowercase(),
+            if self.count == 1 { "" } else { "s" },
+            self.direction.0,
+            self.direction.1,
+            self.phase,
+        )?;
+        if self.count > 1 {
+            write!(f, ", one every {:.1} generations", (self.last - self.first) as f64 / (self.count - 1) as f64)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    fn game(rle: &str, (x, y): (isize, isize), width: usize, height: usize) -> Game {
+        let generation = rle::pattern(rle);
+        Game::new(generation.crop(-x, -y, width, height), None)
+    }
+
+    fn run(game: &mut Game, detector: &mut EscapeDetector, generations: u64) {
+        for _ in 0..generations {
+            game.step();
+            detector.observe(game);
+        }
+    }
+
+    #[test]
+    fn test_escape_counts_glider_once() {
+        let mut game = game("x = 3, y = 3\nbo$2bo$3o!", (0, 0), 40, 40);
+        let mut detector = EscapeDetector::new(&Rule::default(), false);
+        run(&mut game, &mut detector, 200);
+        assert_eq!(1, detector.escapes().len());
+        let escape = &detector.escapes()[0];
+        assert_eq!(("Glider", (1, 1)), (escape.name, escape.direction));
+    }
+
+    #[test]
+    fn test_escape_deletes_glider() {
+        let mut game = game("x = 3, y = 3\nbo$2bo$3o!", (0, 0), 40, 40);
+        let mut detector = EscapeDetector::new(&Rule::default(), true);
+        run(&mut game, &mut detector, 200);
+        assert_eq!(1, detector.escapes().len());
+        assert_eq!(0, game.generation().population());
+    }
+
+    #[test]
+    fn test_escape_ignores_ship_heading_inwards() {
+        // A glider by the top left corner, heading away from it.
+        let mut game = game("x = 3, y = 3\nbo$2bo$3o!", (0, 0), 40, 40);
+        let mut detector = EscapeDetector::new(&Rule::default(), true);
+        detector.observe(&mut game);
+        assert!(detector.escapes().is_empty());
+        assert_eq!(5, game.generation().population());
+    }
+
+    #[test]
+    fn test_escape_lwss() {
+        let mut game = game("x = 5, y = 4\nbo2bo$o4b$o3bo$4o!", (8, 8), 40, 20);
+        let mut detector = EscapeDetector::new(&Rule::default(), true);
+        run(&mut game, &mut detector, 20);
+        assert_eq!(1, detector.escapes().len());
+        assert_eq!(("Lightweight spaceship", (-1, 0)), (detector.escapes()[0].name, detector.escapes()[0].direction));
+    }
+
+    #[test]
+    fn test_escape_gun_streams() {
+        let gun = include_str!("../data/glidergun.rle");
+        let mut game = game(gun, (0, 0), 60, 60);
+        let mut detector = EscapeDetector::new(&Rule::default(), true);
+        run(&mut game, &mut detector, 300);
+        let streams = detector.streams();
+        assert_eq!(1, streams.len());
+        assert_eq!(("Glider", (1, 1)), (streams[0].name, streams[0].direction));
+        assert_eq!(30, (streams[0].last - streams[0].first) / (streams[0].count - 1));
+    }
+
+    #[test]
+    fn test_stream_display() {
+        let stream = Stream{name: "Glider", direction: (1, 1), phase: 2, count: 3, first: 100, last: 160};
+        assert_eq!("3 gliders moving (1, 1) in phase 2, one every 30.0 generations", stream.to_string());
+    }
+}
@@ -1,21 +1,69 @@
+use crate::automaton::Automaton;
 use crate::generation::Generation;
-use crate::rule::Rule;
-use std::{
-    thread,
-    time::{Duration, Instant},
-};
+use crate::rule::{Chance, Rule};
+use crate::stats::{Record, StatsWriter};
+use std::collections::VecDeque;
 
+/// Runs a life-like rule, remembering as many past generations as fit in its
+/// history memory so that it can step back. Stepping forwards again after
+/// stepping back recomputes generations, so with chances they may differ.
+/// Statistics can be written for every generation stepped to, once each;
+/// if writing them fails, that is reported and they are given up.
 pub struct Game {
     current_generation: Generation,
     rule: Rule,
     neighbourhood: Vec<(i32, i32)>,
+    chance: Chance,
+    rng: fastrand::Rng,
+    generation_count: u64,
+    history: VecDeque<Generation>,
+    history_length: usize,
+    stats: Option<StatsWriter>,
 }
 
 impl Game {
     pub fn new(current_generation: Generation, rule: Option<Rule>) -> Game {
         let rule = rule.unwrap_or(Rule::default());
         let neighbourhood = rule.neighbour_pattern.generate(1);
-        Game{current_generation, rule, neighbourhood}
+        Game{current_generation, rule, neighbourhood, chance: Chance::certain(), rng: fastrand::Rng::new(), generation_count: 0, history: VecDeque::new(), history_length: 0, stats: None}
+    }
+
+    /// Remembers as many generations as fit in roughly `bytes` of memory.
+    pub fn set_history_memory(&mut self, bytes: usize) {
+        let grid = &self.current_generation.grid;
+        let cells = grid.len() * grid.first().map_or(0, |row| row.len());
+        self.history_length = bytes / cells.max(1);
+        while self.history.len() > self.history_length {
+            self.history.pop_front();
+        }
+    }
+
+    /// Writes statistics for the current generation and each one after it.
+    /// Collection stops if they can't be written.
+    pub fn set_stats(&mut self, stats: StatsWriter) {
+        self.stats = Some(stats);
+        self.write_stats(Record::between(self.generation_count, None, &self.current_generation));
+    }
+
+    /// Writes `record`, giving up on statistics with a message on stderr if
+    /// it can't be written.
+    fn write_stats(&mut self, record: Record) {
+        if let Some(Err(err)) = self.stats.as_mut().map(|stats| stats.write(&record)) {
+            eprintln!("Could not write statistics, so no more will be written: {}", err);
+            self.stats = None;
+        }
+    }
+
+    pub fn set_chance(&mut self, chance: Chance, seed: u64) {
+        self.chance = chance;
+        self.rng = fastrand::Rng::with_seed(seed);
+    }
+
+    fn apply(&self, alive: &bool, neigbours: &u32) -> bool {
+        if self.chance.is_certain() {
+            return self.rule.apply(alive, neigbours);
+        }
+        self.chance.apply(&self.rng, &self.rule, alive, neigbours)
     }
 
     pub fn next_generation(&self) -> Generation {
@@ -24,36 +72,69 @@ impl Game {
 
         for (y, row) in neigbours.iter().enumerate() {
             for (x, cell) in row.iter().enumerate() {
-                if self.rule.apply(&self.current_generation.alive(&x, &y), &(*cell as u32)) {
+                if self.apply(&self.current_generation.alive(&x, &y), &(*cell as u32)) {
                     grid[y][x] = true;
                 }
             }
         }
-        return Generation::new(grid);
+        Generation::new(grid)
+    }
+}
+
+impl Automaton for Game {
+    fn generation(&self) -> &Generation {
+        &self.current_generation
     }
 
-    pub fn step(&mut self) {
-        self.current_generation = self.next_generation();
+    fn generation_count(&self) -> u64 {
+        self.generation_count
     }
 
-    pub fn display_grid(&self) {
-        let display = self.current_generation.to_string();
-        print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
-        print!("{}", display);
+    fn rule(&self) -> String {
+        self.rule.to_string()
     }
 
-    pub fn run(&mut self, delay: &u32) {
-        let delay = Duration::new(0, delay*1_000_000);
-        let mut elapsed = Duration::new(0, 0);
-        self.display_grid();
-        loop {
-            if delay > elapsed {
-                thread::sleep(delay - elapsed);
+    fn step(&mut self) {
+        let next = self.next_generation();
+        if self.stats.is_some() {
+            self.write_stats(Record::between(self.generation_count + 1, Some(&self.current_generation), &next));
+        }
+        let previous = std::mem::replace(&mut self.current_generation, next);
+        if self.history_length > 0 {
+            if self.history.len() == self.history_length {
+                self.history.pop_front();
             }
-            let now = Instant::now();
-            self.step();
-            self.display_grid();
-            elapsed = now.elapsed();
+            self.history.push_back(previous);
+        }
+        self.generation_count += 1;
+    }
+
+    fn step_back(&mut self) -> bool {
+        match self.history.pop_back() {
+            Some(previous) => {
+                self.current_generation = previous;
+                self.generation_count -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn remembered(&self) -> u64 {
+        self.history.len() as u64
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, alive: bool) {
+        self.current_generation.grid[y][x] = alive;
+    }
+
+    fn reset(&mut self, generation: Generation) {
+        self.current_generation = generation;
+        self.generation_count = 0;
+        self.history.clear();
+        if let Some(stats) = self.stats.as_mut() {
+            stats.restart();
+            self.write_stats(Record::between(0, None, &self.current_generation));
         }
     }
 }
@@ -61,6 +142,7 @@ impl Game {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::automaton::Automaton;
     use rstest::rstest;
 
     type Grid = Vec<Vec<bool>>;
@@ -100,4 +182,113 @@ mod tests {
         let gen = game.next_generation();
         assert_eq!(expected, gen.grid);
     }
+
+    #[test]
+    fn test_game_chance_is_reproducible() {
+        let run = |seed| {
+            let mut game = Game::new(Generation::new(vec![vec![false; 8]; 8]), None);
+            game.set_chance(Chance{birth: 1.0, survival: 1.0, noise: 0.2}, seed);
+            game.step();
+            game.step();
+            game.generation().grid.clone()
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+
+    fn glider() -> Game {
+        Game::new(Generation::new(vec![
+            vec![false, true, false, false, false],
+            vec![false, false, true, false, false],
+            vec![true, true, true, false, false],
+            vec![false, false, false, false, false],
+            vec![false, false, false, false, false],
+        ]), None)
+    }
+
+    #[test]
+    fn test_game_writes_stats() {
+        let filename = std::env::temp_dir().join("life_test_game_stats.csv");
+        let filename = filename.to_str().unwrap();
+        {
+            let mut game = glider();
+            game.set_stats(StatsWriter::create(filename).unwrap());
+            game.step();
+            game.step();
+        }
+        let contents = std::fs::read_to_string(filename).unwrap();
+        std::fs::remove_file(filename).unwrap();
+        assert_eq!(
+            vec![
+                "run,generation,population,births,deaths,left,top,width,height,density",
+                "0,0,5,0,0,0,0,3,3,0.5556",
+                "0,1,5,2,2,0,1,3,3,0.5556",
+                "0,2,5,2,2,0,1,3,3,0.5556",
+            ],
+            contents.lines().collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn test_game_writes_stats_once_per_generation() {
+        let filename = std::env::temp_dir().join("life_test_game_stats_rewind.jsonl");
+        let filename = filename.to_str().unwrap();
+        {
+            let mut game = glider();
+            game.set_history_memory(1000);
+            game.set_stats(StatsWriter::create(filename).unwrap());
+            game.step();
+            game.step_back();
+            game.step();
+            game.goto(2).unwrap();
+            game.goto(0).unwrap();
+            game.goto(2).unwrap();
+            game.reset(Generation::new(glider().generation().grid.clone()));
+            game.step();
+        }
+        let contents = std::fs::read_to_string(filename).unwrap();
+        std::fs::remove_file(filename).unwrap();
+        let rows: Vec<&str> = contents.lines().map(|line| &line[..line.find(", \"population").unwrap()]).collect();
+        assert_eq!(
+            vec![
+                "{\"run\": 0, \"generation\": 0",
+                "{\"run\": 0, \"generation\": 1",
+                "{\"run\": 0, \"generation\": 2",
+                "{\"run\": 1, \"generation\": 0",
+                "{\"run\": 1, \"generation\": 1",
+            ],
+            rows,
+        );
+    }
+
+    #[test]
+    fn test_game_step_back() {
+        let mut game = glider();
+        game.set_history_memory(50); // two generations of 25 cells
+        assert!(!game.step_back());
+        let start = game.generation().grid.clone();
+        game.step();
+        let first = game.generation().grid.clone();
+        game.step();
+        game.step();
+        assert_eq!(2, game.remembered());
+        assert!(game.step_back());
+        assert!(game.step_back());
+        assert!(!game.step_back());
+        assert_eq!((1, &first), (game.generation_count(), &game.generation().grid));
+        assert_eq!(Err("That generation is no longer remembered"), game.goto(0));
+        assert_eq!(Ok(()), game.goto(4));
+        assert_eq!(Ok(()), game.goto(2));
+        assert_eq!((2, 0), (game.generation_count(), game.remembered()));
+        game.reset(Generation::new(start));
+        assert_eq!(0, game.remembered());
+    }
+
+    #[test]
+    fn test_game_without_history() {
+        let mut game = glider();
+        game.step();
+        assert!(!game.step_back());
+        assert_eq!(Err("That generation is no longer remembered"), game.goto(0));
+    }
 }
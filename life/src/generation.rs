@@ -1,36 +1,134 @@
+use crate::density::{DensityMap, Fill};
+use crate::render::Renderer;
+use crate::symmetry::Symmetry;
+use std::collections::hash_map::DefaultHasher;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
 fn add(unsigned: &usize, signed: &i32) -> usize {
     if *signed < 0 {
-        return unsigned.wrapping_sub(signed.abs() as usize)
+        return unsigned.wrapping_sub(signed.unsigned_abs() as usize)
     }
-    return unsigned.wrapping_add(*signed as usize)
+    unsigned.wrapping_add(*signed as usize)
 }
 
 type Grid = Vec<Vec<bool>>;
 
+/// Parses a width or height, which must be at least one cell.
+pub fn parse_size(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(size) if size > 0 => Ok(size),
+        _ => Err(format!("{} is not a size of at least 1", s)),
+    }
+}
+
+#[derive(Debug, PartialEq)]
 pub struct Generation {
     pub grid: Grid,
 }
 
 impl Generation {
     pub fn new(grid: Grid) -> Generation {
-        return Generation{grid}
+        Generation{grid}
     }
 
-    pub fn soup(width: usize, height: usize, density: f64) -> Generation {
+    pub fn soup(rng: &fastrand::Rng, width: usize, height: usize, density: &DensityMap, fill: &Fill) -> Generation {
+        let mut densities = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                densities.push(density.at(x, y, width, height));
+            }
+        }
+        let chosen = fill.choose(rng, &densities);
+        Generation::new((0..height).map(|y| chosen[y * width..(y + 1) * width].to_vec()).collect())
+    }
+
+    /// A soup with the given symmetry, `soup_width` by `soup_height` as found
+    /// by `Symmetry::fit`, placed in the middle of an otherwise empty `width`
+    /// by `height` field. Only one cell of each symmetric orbit is chosen, the
+    /// rest are copied from it.
+    pub fn symmetric_soup(rng: &fastrand::Rng, width: usize, height: usize, density: &DensityMap, fill: &Fill, symmetry: &Symmetry, (soup_width, soup_height): (usize, usize)) -> Generation {
+        let mut representatives = Vec::new();
+        let mut densities = Vec::new();
+        for y in 0..soup_height {
+            for x in 0..soup_width {
+                if symmetry.representative(x, y, soup_width, soup_height) == (x, y) {
+                    representatives.push((x, y));
+                    densities.push(density.at(x, y, soup_width, soup_height));
+                }
+            }
+        }
+        let mut soup = vec![vec![false; soup_width]; soup_height];
+        for ((x, y), alive) in representatives.into_iter().zip(fill.choose(rng, &densities)) {
+            soup[y][x] = alive;
+        }
+        for y in 0..soup_height {
+            for x in 0..soup_width {
+                let (i, j) = symmetry.representative(x, y, soup_width, soup_height);
+                soup[y][x] = soup[j][i];
+            }
+        }
+        let left = (width - soup_width) / 2;
+        let top = (height - soup_height) / 2;
         let mut grid = vec![vec![false; width]; height];
-        let amount = ((width*height) as f64 *density).ceil() as i32;
-        for _ in 0..amount {
-            let x = fastrand::usize(..width);
-            let y = fastrand::usize(..height);
-            grid[y][x] = true;
+        for (y, row) in soup.into_iter().enumerate() {
+            grid[top + y][left..left + soup_width].copy_from_slice(&row);
         }
-        return Generation::new(grid)
+        Generation::new(grid)
+    }
+
+    pub fn population(&self) -> usize {
+        self.grid.iter().map(|row| row.iter().filter(|cell| **cell).count()).sum()
+    }
+
+    /// The cells in the `width` by `height` box at `(left, top)`, which may
+    /// reach past the grid, where every cell is dead.
+    pub fn crop(&self, left: isize, top: isize, width: usize, height: usize) -> Generation {
+        let grid = (0..height).map(|y| (0..width).map(|x| {
+            let (x, y) = (left + x as isize, top + y as isize);
+            y >= 0 && x >= 0 && self.grid.get(y as usize).and_then(|row| row.get(x as usize)) == Some(&true)
+        }).collect()).collect();
+        Generation::new(grid)
+    }
+
+    /// The left, top, width and height of the smallest box holding every
+    /// live cell, or `None` when nothing is alive.
+    pub fn bounding_box(&self) -> Option<(usize, usize, usize, usize)> {
+        let rows: Vec<usize> = (0..self.grid.len()).filter(|y| self.grid[*y].contains(&true)).collect();
+        let columns: Vec<usize> = (0..self.grid.first().map_or(0, |row| row.len()))
+            .filter(|x| self.grid.iter().any(|row| row[*x]))
+            .collect();
+        let (top, bottom) = (*rows.first()?, *rows.last()?);
+        let (left, right) = (columns[0], columns[columns.len() - 1]);
+        Some((left, top, right - left + 1, bottom - top + 1))
+    }
+
+    fn hash_from(&self, left: usize, top: usize) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for (y, row) in self.grid.iter().enumerate() {
+            for (x, alive) in row.iter().enumerate() {
+                if *alive {
+                    (x - left, y - top).hash(&mut hasher);
+                }
+            }
+        }
+        hasher.finish()
+    }
+
+    /// Hashes the live cells where they are.
+    pub fn position_hash(&self) -> u64 {
+        self.hash_from(0, 0)
+    }
+
+    /// Hashes the live cells relative to their bounding box, so that the
+    /// same shape hashes the same wherever it is.
+    pub fn shape_hash(&self) -> u64 {
+        let (left, top, _, _) = self.bounding_box().unwrap_or((0, 0, 0, 0));
+        self.hash_from(left, top)
     }
 
     pub fn alive(&self, x: &usize, y: &usize) -> bool {
-        return self.grid[*y][*x];
+        self.grid[*y][*x]
     }
 
     pub fn neigbour_counts(&self, neighbourhood: &Vec<(i32, i32)>) -> Vec<Vec<i8>> {
@@ -41,8 +139,8 @@ impl Generation {
             for (x, cell) in row.iter().enumerate() {
                 if *cell {
                     for (i, j) in neighbourhood {
-                        let tmp_y = add(&y, &j);
-                        let tmp_x = add(&x, &i);
+                        let tmp_y = add(&y, j);
+                        let tmp_x = add(&x, i);
                         if tmp_x < width && tmp_y < height {
                             result[tmp_y][tmp_x] += 1
                         }
@@ -50,32 +148,13 @@ impl Generation {
                 }
             }
         }
-        return result;
+        result
     }
 }
 
 impl fmt::Display for Generation {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut result: String = "".to_owned();
-        for pair in self.grid.chunks(2) {
-            let mut pair_two = &vec![false; pair[0].len()];
-            if pair.len() >= 2 {
-                pair_two = &pair[1];
-            }
-            for (upper, lower) in pair[0].iter().zip(pair_two) {
-                if *upper && *lower {
-                    result += "█"
-                } else if *upper {
-                    result += "▀"
-                } else if *lower {
-                    result += "▄"
-                } else {
-                    result += " "
-                }
-            }
-            result += "\n";
-        }
-        return write!(f, "{}", &result[..result.len()-1]);
+        write!(f, "{}", Renderer::HalfBlock.render(self))
     }
 }
 
@@ -84,6 +163,15 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
+    #[rstest]
+    #[case("1", Ok(1))]
+    #[case("200", Ok(200))]
+    #[case("0", Err("0 is not a size of at least 1".to_owned()))]
+    #[case("-3", Err("-3 is not a size of at least 1".to_owned()))]
+    fn test_parse_size(#[case] size: &str, #[case] expected: Result<usize, String>) {
+        assert_eq!(expected, parse_size(size));
+    }
+
     #[rstest]
     #[case(
         vec![
@@ -113,4 +201,99 @@ mod tests {
         let gen = Generation::new(grid);
         assert_eq!(expected, gen.to_string());
     }
+
+    #[test]
+    fn test_generation_soup_is_seeded() {
+        let soup = |seed| Generation::soup(&fastrand::Rng::with_seed(seed), 16, 16, &DensityMap::Uniform(0.5), &Fill::Exact).grid;
+        assert_eq!(soup(1), soup(1));
+        assert_ne!(soup(1), soup(2));
+    }
+
+    #[test]
+    fn test_generation_soup_without_width() {
+        let soup = Generation::soup(&fastrand::Rng::with_seed(1), 0, 3, &DensityMap::Uniform(0.5), &Fill::Exact);
+        assert_eq!(vec![Vec::<bool>::new(); 3], soup.grid);
+    }
+
+    #[rstest]
+    #[case(DensityMap::Uniform(0.25), Fill::Exact, 64)]
+    #[case(DensityMap::Horizontal(0.0, 0.5), Fill::Exact, 64)]
+    #[case(DensityMap::Uniform(1.0), Fill::Bernoulli, 256)]
+    fn test_generation_soup_population(#[case] density: DensityMap, #[case] fill: Fill, #[case] expected: usize) {
+        let rng = fastrand::Rng::with_seed(1);
+        assert_eq!(expected, Generation::soup(&rng, 16, 16, &density, &fill).population());
+    }
+
+    #[rstest]
+    #[case("C2_4", |grid: &Grid, x: usize, y: usize| grid[9 - y][9 - x])]
+    #[case("D2_+2", |grid: &Grid, x: usize, y: usize| grid[y][9 - x])]
+    #[case("D8_4", |grid: &Grid, x: usize, y: usize| grid[x][9 - y])]
+    #[case("D8_4", |grid: &Grid, x: usize, y: usize| grid[y][9 - x])]
+    fn test_generation_symmetric_soup(#[case] symmetry: Symmetry, #[case] image: fn(&Grid, usize, usize) -> bool) {
+        let rng = fastrand::Rng::with_seed(3);
+        let area = symmetry.fit(5, 10, 10).unwrap();
+        let grid = Generation::symmetric_soup(&rng, 10, 10, &DensityMap::Uniform(0.5), &Fill::Exact, &symmetry, area).grid;
+        for y in 0..10 {
+            for x in 0..10 {
+                assert_eq!(grid[y][x], image(&grid, x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_generation_symmetric_soup_is_centred() {
+        let rng = fastrand::Rng::with_seed(3);
+        let grid = Generation::symmetric_soup(&rng, 10, 8, &DensityMap::Uniform(0.5), &Fill::Exact, &"C1".parse().unwrap(), (4, 4)).grid;
+        assert_eq!((10, 8), (grid[0].len(), grid.len()));
+        for (y, row) in grid.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                assert!(!cell || ((3..7).contains(&x) && (2..6).contains(&y)));
+            }
+        }
+    }
+
+
+    #[rstest]
+    #[case(vec![vec![false; 3]; 2], None)]
+    #[case(
+        vec![
+            vec![false, false, false, false],
+            vec![false, true, false, false],
+            vec![false, false, false, true],
+        ],
+        Some((1, 1, 3, 2)),
+    )]
+    fn test_generation_bounding_box(#[case] grid: Grid, #[case] expected: Option<(usize, usize, usize, usize)>) {
+        assert_eq!(expected, Generation::new(grid).bounding_box());
+    }
+
+    #[test]
+    fn test_generation_hashes() {
+        let block = |x: usize, y: usize| {
+            let mut grid = vec![vec![false; 6]; 6];
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                grid[y + dy][x + dx] = true;
+            }
+            Generation::new(grid)
+        };
+        assert_eq!(block(1, 2).position_hash(), block(1, 2).position_hash());
+        assert_ne!(block(1, 2).position_hash(), block(3, 0).position_hash());
+        assert_eq!(block(1, 2).shape_hash(), block(3, 0).shape_hash());
+    }
+
+    #[test]
+    fn test_generation_crop() {
+        let generation = Generation::new(vec![
+            vec![true, false],
+            vec![false, true],
+        ]);
+        assert_eq!(
+            vec![
+                vec![false, false, false],
+                vec![false, true, false],
+                vec![false, false, true],
+            ],
+            generation.crop(-1, -1, 3, 3).grid,
+        );
+    }
 }
@@ -0,0 +1,220 @@
+use crate::analysis::{self, Class};
+use crate::apgcode;
+use crate::generation::Generation;
+use crate::objects;
+use crate::rle;
+use crate::rule::Rule;
+use std::collections::HashMap;
+
+type Grid = Vec<Vec<bool>>;
+
+// How long a pattern in the library may take to settle.
+const SETTLE: u64 = 100;
+
+const PATTERNS: &[&str] = &[
+    include_str!("../data/block.rle"),
+    include_str!("../data/beehive.rle"),
+    include_str!("../data/loaf.rle"),
+    include_str!("../data/boat.rle"),
+    include_str!("../data/ship.rle"),
+    include_str!("../data/tub.rle"),
+    include_str!("../data/pond.rle"),
+    include_str!("../data/eater1.rle"),
+    include_str!("../data/blinker.rle"),
+    include_str!("../data/toad.rle"),
+    include_str!("../data/beacon.rle"),
+    include_str!("../data/pulsar.rle"),
+    include_str!("../data/pentadecathlon.rle"),
+    include_str!("../data/glider.rle"),
+    include_str!("../data/lwss.rle"),
+    include_str!("../data/mwss.rle"),
+    include_str!("../data/hwss.rle"),
+    include_str!("../data/rpentomino.rle"),
+    include_str!("../data/glidergun.rle"),
+];
+
+/// A pattern which separating into objects would split up, such as a gun,
+/// so it is looked for whole, in each orientation.
+struct Compound {
+    rule: String,
+    name: &'static str,
+    shapes: Vec<Grid>,
+}
+
+/// Names for well known patterns, looked up by rule and canonical form.
+/// Patterns which settle are known in every phase, others, such as guns,
+/// only in the phase they are stored in.
+pub struct Library {
+    names: HashMap<(String, String), &'static str>,
+    compounds: Vec<Compound>,
+}
+
+/// Where `shape` appears in `grid` with nothing alive in the cells around it.
+fn occurrences(grid: &Grid, shape: &Grid) -> Vec<(usize, usize)> {
+    let (height, width) = (grid.len() as isize, grid.first().map_or(0, |row| row.len()) as isize);
+    let (shape_height, shape_width) = (shape.len() as isize, shape[0].len() as isize);
+    let mut found = Vec::new();
+    for y in 0..=height - shape_height {
+        for x in 0..=width - shape_width {
+            let matches = (-1..=shape_height).all(|dy| (-1..=shape_width).all(|dx| {
+                let (cx, cy) = (x + dx, y + dy);
+                if cx < 0 || cy < 0 || cx >= width || cy >= height {
+                    return true;
+                }
+                let inside = (0..shape_height).contains(&dy) && (0..shape_width).contains(&dx);
+                grid[cy as usize][cx as usize] == (inside && shape[dy as usize][dx as usize])
+            }));
+            if matches {
+                found.push((x as usize, y as usize));
+            }
+        }
+    }
+    found
+}
+
+impl Library {
+    pub fn new() -> Library {
+        let mut names = HashMap::new();
+        let mut compounds = Vec::new();
+        for contents in PATTERNS {
+            let (grid, rule) = rle::parse_file(contents).unwrap();
+            let name = rle::name(contents).unwrap();
+            let rule = rule.unwrap_or(Rule::default());
+            let generation = Generation::new(grid);
+            let analysis = analysis::analyze(generation.grid.clone(), Some(rule.clone()), SETTLE);
+            let phases = match (&analysis.class, analysis.cycle) {
+                (Class::StillLife | Class::Oscillator | Class::Spaceship, Some(cycle)) if cycle.start == 0 => {
+                    apgcode::phases(&generation, Some(rule.clone()), &cycle)
+                }
+                _ => vec![generation],
+            };
+            for phase in phases {
+                names.insert((rule.to_string(), apgcode::canonical(&phase)), name);
+                if objects::separate(&phase, Some(rule.clone()), 1).len() > 1 {
+                    let (left, top, width, height) = phase.bounding_box().unwrap();
+                    let mut shapes = apgcode::orientations(&phase.crop(left as isize, top as isize, width, height).grid);
+                    shapes.sort();
+                    shapes.dedup();
+                    compounds.push(Compound{rule: rule.to_string(), name, shapes});
+                }
+            }
+        }
+        Library{names, compounds}
+    }
+
+    /// The name of the pattern made up of the live cells of `generation`.
+    pub fn name(&self, generation: &Generation, rule: &Rule) -> Option<&'static str> {
+        self.names.get(&(rule.to_string(), apgcode::canonical(generation))).copied()
+    }
+
+    /// The known objects in `generation`, by name and top left corner, in
+    /// reading order. Patterns made of several parts are picked out whole
+    /// before the rest is separated into objects.
+    pub fn find(&self, generation: &Generation, rule: &Rule) -> Vec<(&'static str, usize, usize)> {
+        let mut rest = generation.grid.clone();
+        let mut found = Vec::new();
+        for compound in self.compounds.iter().filter(|compound| compound.rule == rule.to_string()) {
+            for shape in &compound.shapes {
+                for (x, y) in occurrences(&rest, shape) {
+                    found.push((compound.name, x, y));
+                    for (dy, row) in shape.iter().enumerate() {
+                        for (dx, alive) in row.iter().enumerate() {
+                            rest[y + dy][x + dx] &= !alive;
+                        }
+                    }
+                }
+            }
+        }
+        found.extend(objects::separate(&Generation::new(rest), Some(rule.clone()), 1).into_iter()
+            .filter_map(|object| Some((self.name(&object.generation, rule)?, object.x, object.y))));
+        found.sort_by_key(|(_, x, y)| (*y, *x));
+        found
+    }
+}
+
+/// Sums up the objects found, such as `3 block, 2 blinker, glider`, the
+/// most common first.
+pub fn summary(found: &[(&str, usize, usize)]) -> String {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for (name, _, _) in found {
+        match counts.iter_mut().find(|(counted, _)| counted == name) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((name, 1)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    counts.iter()
+        .map(|(name, count)| match count {
+            1 => name.to_lowercase(),
+            _ => format!("{} {}", count, name.to_lowercase()),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rle::pattern;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("x = 2, y = 2\n2o$2o!", Some("Block"))]
+    #[case("x = 3, y = 3\n3o$2bo$bo!", Some("Glider"))]
+    #[case("x = 3, y = 3\nobo$b2o$bo!", Some("Glider"))]
+    #[case("x = 1, y = 3\no$o$o!", Some("Blinker"))]
+    #[case("x = 5, y = 4\no2bo$4bo$o3bo$b4o!", Some("Lightweight spaceship"))]
+    #[case("x = 3, y = 3\nbo$3o$2bo!", Some("R-pentomino"))]
+    #[case("x = 3, y = 3\nobo$obo$bo!", None)]
+    fn test_library_name(#[case] rle: &str, #[case] expected: Option<&str>) {
+        assert_eq!(expected, Library::new().name(&pattern(rle), &Rule::default()));
+    }
+
+    #[test]
+    fn test_library_name_every_phase() {
+        let library = Library::new();
+        let pulsar = pattern(include_str!("../data/pulsar.rle"));
+        let cycle = analysis::analyze(pulsar.grid.clone(), None, 10).cycle.unwrap();
+        for phase in apgcode::phases(&pulsar, None, &cycle) {
+            assert_eq!(Some("Pulsar"), library.name(&phase, &Rule::default()));
+        }
+    }
+
+    #[test]
+    fn test_library_name_gun() {
+        let gun = pattern(include_str!("../data/glidergun.rle"));
+        assert_eq!(Some("Gosper glider gun"), Library::new().name(&gun, &Rule::default()));
+    }
+
+    #[test]
+    fn test_library_find_gun() {
+        let gun = pattern(include_str!("../data/glidergun.rle"));
+        let library = Library::new();
+        assert_eq!(vec![("Gosper glider gun", 0, 0)], library.find(&gun, &Rule::default()));
+        // Flipped, and with a block beside it.
+        let flipped = Generation::new(apgcode::orientations(&gun.grid)[1].clone()).crop(0, -4, 36, 13);
+        let mut grid = flipped.grid;
+        grid[0][0..2].copy_from_slice(&[true, true]);
+        grid[1][0..2].copy_from_slice(&[true, true]);
+        let found = library.find(&Generation::new(grid), &Rule::default());
+        assert_eq!(vec![("Block", 0, 0), ("Gosper glider gun", 0, 4)], found);
+    }
+
+    #[test]
+    fn test_library_name_depends_on_rule() {
+        let rule = "B36/S23".parse().unwrap();
+        assert_eq!(None, Library::new().name(&pattern("x = 2, y = 2\n2o$2o!"), &rule));
+    }
+
+    #[test]
+    fn test_library_find() {
+        let found = Library::new().find(&pattern("x = 10, y = 6\nbo$2bo5b2o$3o5b2o3$4b3o!"), &Rule::default());
+        assert_eq!(vec![("Glider", 0, 0), ("Block", 8, 1), ("Blinker", 4, 5)], found);
+        assert_eq!("blinker, block, glider", summary(&found));
+    }
+
+    #[test]
+    fn test_summary_counts() {
+        assert_eq!("2 block, glider", summary(&[("Block", 0, 0), ("Glider", 5, 5), ("Block", 9, 9)]));
+    }
+}
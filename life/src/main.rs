@@ -1,12 +1,47 @@
+mod analysis;
+mod apgcode;
+mod automaton;
+mod census;
+mod colour;
+mod density;
+mod elementary;
+mod escape;
 mod generation;
 mod game;
+mod library;
 mod rule;
+mod margolus;
+mod objects;
+mod period;
+mod predecessor;
+mod render;
 mod rle;
+mod screen;
+mod stats;
+mod symmetry;
+mod tui;
+mod turmite;
+mod viewport;
 
+use std::time::Instant;
 use structopt::StructOpt;
+use automaton::Automaton;
+use census::{Census, Format};
+use colour::{Depth, Palette, Scheme};
+use density::{DensityMap, Fill};
+use elementary::{Elementary, ElementaryRule};
+use escape::EscapeDetector;
 use generation::Generation;
 use game::Game;
-use rule::Rule;
+use library::Library;
+use margolus::{BlockGame, BlockRule};
+use period::PeriodDetector;
+use render::Renderer;
+use rule::{Chance, Rule};
+use stats::StatsWriter;
+use symmetry::Symmetry;
+use turmite::{Turmite, TurmiteRule};
+use tui::Tui;
 
 #[derive(Debug, StructOpt)]
 #[structopt(name="life", about="A rust implementation of John Conway's Game of Life")]
@@ -15,45 +50,373 @@ struct Opt {
     cmd: Subcommand,
     #[structopt(short="r", long="rules", global=true)]
     rule: Option<Rule>,
+    #[structopt(long="block", help="Run a Margolus block rule such as critters or MS,D0;8;4;...", global=true)]
+    block: Option<BlockRule>,
     #[structopt(long="delay", default_value="16", about="Delay between generations (in miliseconds)", global=true)]
     delay: u32,
+    #[structopt(long="birth-chance", default_value="1", parse(try_from_str=rule::parse_probability), help="Probability that a birth allowed by the rule happens", global=true)]
+    birth_chance: f64,
+    #[structopt(long="survival-chance", default_value="1", parse(try_from_str=rule::parse_probability), help="Probability that a survival allowed by the rule happens", global=true)]
+    survival_chance: f64,
+    #[structopt(long="noise", default_value="0", parse(try_from_str=rule::parse_probability), help="Probability that any other dead cell is born spontaneously", global=true)]
+    noise: f64,
+    #[structopt(long="render", default_value="half-block", help="Draw cells as half-block, braille or ascii characters", global=true)]
+    render: Renderer,
+    #[structopt(long="colour", help="Colour cells by age, status (born, survived or died) or a fading trail of dead cells", global=true)]
+    colour: Option<Scheme>,
+    #[structopt(long="colour-depth", default_value="256", help="256 or truecolor", global=true)]
+    colour_depth: Depth,
+    #[structopt(long="trail", default_value="8", help="Generations a dead cell's trail takes to fade", global=true)]
+    trail: u32,
+    #[structopt(long="save", default_value="pattern.rle", help="File the grid is saved to from edit mode", global=true)]
+    save: String,
+    #[structopt(long="history-memory", default_value="64", help="Megabytes of past generations kept for stepping back", global=true)]
+    history_memory: usize,
+    #[structopt(long="generations", help="Run this many generations without drawing, then print the result as RLE", global=true)]
+    generations: Option<u64>,
+    #[structopt(long="output", help="Write the RLE from --generations or predecessor, or the census from search, to this file instead", global=true)]
+    output: Option<String>,
+    #[structopt(long="stop-when-stable", help="Stop, or pause on screen, once the pattern starts repeating", global=true)]
+    stop_when_stable: bool,
+    #[structopt(long="objects", help="After --generations, list the separate objects left", global=true)]
+    objects: bool,
+    #[structopt(long="escapes", help="Count gliders and other spaceships leaving the grid", global=true)]
+    escapes: bool,
+    #[structopt(long="delete-escaping", help="Remove spaceships at the edge of the grid instead of letting them crash, counting them", global=true)]
+    delete_escaping: bool,
+    #[structopt(long="stats-out", help="Write population, births, deaths, bounding box and density for every generation to this file, as CSV, as JSON Lines if it ends in .jsonl or as a JSON array if it ends in .json", global=true)]
+    stats_out: Option<String>,
+    #[structopt(long="seed", help="Seed for the random number generator, so runs can be repeated", global=true)]
+    seed: Option<u64>,
 }
 
 #[derive(Debug, StructOpt)]
 enum Subcommand {
     Soup {
-        #[structopt(default_value="200")]
+        #[structopt(default_value="200", parse(try_from_str=generation::parse_size))]
         width: usize,
-        #[structopt(default_value="100")]
+        #[structopt(default_value="100", parse(try_from_str=generation::parse_size))]
         height: usize,
-        #[structopt(long="density", default_value="0.25")]
-        density: f64,
+        #[structopt(long="density", default_value="0.25", help="A density from 0 to 1, or a map such as horizontal:0.1:0.5, radial:0.5:0 or checker:0.1:0.6:8")]
+        density: DensityMap,
+        #[structopt(long="fill", default_value="exact", help="exact to fill as many cells as the density asks for, or bernoulli to give each cell its own chance")]
+        fill: Fill,
+        #[structopt(long="symmetry", default_value="C1", help="Symmetry of the soup, named as in apgsearch (C1, C2_4, D8_1...)")]
+        symmetry: Symmetry,
+        #[structopt(long="soup-size", parse(try_from_str=generation::parse_size), help="Grow the soup from a square this size in the middle of the field [default: 16 with symmetry]")]
+        soup_size: Option<usize>,
     },
     File {
         filename: String,
     },
+    /// Classify a pattern as a still life, oscillator or spaceship, with its period, speed, heat and volatility
+    Analyze {
+        filename: String,
+        #[structopt(long="max-generations", default_value="1000", help="Give up if the pattern has not repeated by then")]
+        max_generations: u64,
+    },
+    /// Find a pattern which becomes this one in one generation, or show that none exists
+    Predecessor {
+        filename: String,
+        #[structopt(long="isolated", help="Also require the cells around the pattern to die, so the parent gives exactly the pattern")]
+        isolated: bool,
+    },
+    /// Run the pattern named by an apgcode such as xq4_153, in the middle of the field
+    Apgcode {
+        code: String,
+        #[structopt(default_value="200", parse(try_from_str=generation::parse_size))]
+        width: usize,
+        #[structopt(default_value="100", parse(try_from_str=generation::parse_size))]
+        height: usize,
+    },
+    /// Run many seeded soups until they settle and count the objects left, as apgsearch does
+    Search {
+        #[structopt(long="soups", default_value="100")]
+        soups: u64,
+        #[structopt(long="soup-size", default_value="16", parse(try_from_str=generation::parse_size))]
+        soup_size: usize,
+        #[structopt(long="density", default_value="0.5", help="A density from 0 to 1, or a map such as radial:0.5:0")]
+        density: DensityMap,
+        #[structopt(long="fill", default_value="exact", help="exact or bernoulli")]
+        fill: Fill,
+        #[structopt(long="max-generations", default_value="5000", help="Give up on a soup which has not settled by then")]
+        max_generations: u64,
+        #[structopt(long="format", default_value="csv", help="Write the census as csv or json")]
+        format: Format,
+        #[structopt(long="rare", default_value="1", help="Log the soups behind objects found no more than this many times")]
+        rare: u64,
+    },
+    /// Run a 1D rule such as W30 or T20R2, drawing generations downwards
+    Elementary {
+        #[structopt(name="RULE")]
+        rule: ElementaryRule,
+        #[structopt(default_value="200", parse(try_from_str=generation::parse_size))]
+        width: usize,
+        #[structopt(default_value="100", parse(try_from_str=generation::parse_size))]
+        height: usize,
+        #[structopt(long="density", parse(try_from_str=rule::parse_probability), help="Start from a random row instead of a single cell")]
+        density: Option<f64>,
+    },
+    /// Run Langton's ant or another turmite, given as turns (RL) or a table
+    Turmite {
+        #[structopt(name="RULE")]
+        rule: TurmiteRule,
+        #[structopt(default_value="200", parse(try_from_str=generation::parse_size))]
+        width: usize,
+        #[structopt(default_value="100", parse(try_from_str=generation::parse_size))]
+        height: usize,
+        #[structopt(long="ants", default_value="1")]
+        ants: usize,
+        #[structopt(long="moves", default_value="1", help="Ant moves per generation")]
+        moves: usize,
+    },
+}
+
+fn exit_with(err: &str) -> ! {
+    eprintln!("{}", err);
+    std::process::exit(1);
+}
+
+/// Runs `generations` generations as fast as possible, or until the pattern
+/// repeats if `stop_when_stable`, writes the final grid as RLE and reports on
+/// the run, listing the objects left and the spaceships which escaped if asked
+/// to.
+fn run_headless(automaton: &mut dyn Automaton, generations: u64, stop_when_stable: bool, output: Option<&str>, objects: bool, mut escapes: Option<EscapeDetector>) {
+    let start = Instant::now();
+    let mut detector = PeriodDetector::new();
+    detector.observe(automaton.generation_count(), automaton.generation());
+    while automaton.generation_count() < generations {
+        automaton.step();
+        if let Some(escapes) = escapes.as_mut() {
+            escapes.observe(automaton);
+        }
+        if detector.observe(automaton.generation_count(), automaton.generation()).is_some() && stop_when_stable {
+            break;
+        }
+    }
+    let elapsed = start.elapsed();
+    let generation = automaton.generation();
+    let rule = automaton.rule();
+    match output {
+        Some(filename) => rle::write_file(filename, &generation.grid, Some(&rule)).unwrap_or_else(|err| exit_with(&err.to_string())),
+        None => print!("{}", rle::encode(&generation.grid, Some(&rule))),
+    }
+    eprintln!("Generation {}, population {}", automaton.generation_count(), generation.population());
+    match generation.bounding_box() {
+        Some((x, y, width, height)) => eprintln!("Bounding box {}x{} at ({}, {})", width, height, x, y),
+        None => eprintln!("Bounding box empty"),
+    }
+    match detector.cycle() {
+        Some(cycle) => eprintln!("Repeating with {}", cycle),
+        None => eprintln!("No repetition found"),
+    }
+    if let Some(escapes) = escapes {
+        let streams = escapes.streams();
+        if streams.is_empty() {
+            eprintln!("No spaceships escaped");
+        }
+        for stream in streams {
+            eprintln!("Escaped {}", stream);
+        }
+    }
+    if objects {
+        let Ok(rule) = rule.parse::<Rule>() else {
+            exit_with("Objects can only be separated under life-like rules");
+        };
+        let period = detector.cycle().map_or(1, |cycle| cycle.period);
+        let library = Library::new();
+        for object in objects::separate(generation, Some(rule.clone()), period) {
+            let code = apgcode::encode(&object.generation, Some(rule.clone()), 1000);
+            let name = library.name(&object.generation, &rule).map_or(String::new(), |name| format!(" ({})", name));
+            eprintln!("{}{} at ({}, {})", code.as_deref().unwrap_or("unknown"), name, object.x, object.y);
+        }
+    }
+    eprintln!(
+        "Ran in {:.3}s ({:.1} gen/s)",
+        elapsed.as_secs_f64(),
+        automaton.generation_count() as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+    );
+}
+
+/// Runs `soups` soups, each seeded with the next number on from `seed`, and
+/// writes a census of the objects they settle into. The soups behind rare
+/// objects are logged so they can be watched with `life soup --soup-size`
+/// and `--seed`, which grows the same soup.
+#[allow(clippy::too_many_arguments)]
+fn search(seed: u64, soups: u64, size: usize, density: &DensityMap, fill: &Fill, rule: Rule, max_generations: u64, format: Format, rare: u64, output: Option<&str>) {
+    let start = Instant::now();
+    let mut census = Census::new();
+    for soup_seed in (0..soups).map(|index| seed.wrapping_add(index)) {
+        let soup = Generation::soup(&fastrand::Rng::with_seed(soup_seed), size, size, density, fill);
+        census.add(soup_seed, census::ash(&soup, &rule, max_generations));
+    }
+    let written = census.write(format, rare);
+    match output {
+        Some(filename) => std::fs::write(filename, written).unwrap_or_else(|err| exit_with(&err.to_string())),
+        None => print!("{}", written),
+    }
+    for (code, seeds) in census.rare(rare) {
+        for seed in seeds {
+            eprintln!("Rare {} in soup {}", code, seed);
+        }
+    }
+    if !census.unsettled.is_empty() {
+        eprintln!("{} soups did not settle within {} generations", census.unsettled.len(), max_generations);
+    }
+    eprintln!("Searched {} soups in {:.3}s", census.soups, start.elapsed().as_secs_f64());
 }
 
 fn main() {
     let opt = Opt::from_args();
-    let generation: Generation;
-    let mut rule: Option<Rule> = None;
-    match opt.cmd {
-        Subcommand::Soup {width, height, density} => {
-            generation = Generation::soup(width, height, density);
+    let seed = opt.seed.unwrap_or_else(|| fastrand::u64(..));
+    let chance = Chance{birth: opt.birth_chance, survival: opt.survival_chance, noise: opt.noise};
+    if opt.seed.is_none() && (matches!(opt.cmd, Subcommand::Soup {..} | Subcommand::Search {..}) || !chance.is_certain()) {
+        eprintln!("Using seed {}", seed);
+    }
+    let rng = fastrand::Rng::with_seed(seed);
+    let palette = opt.colour.map(|scheme| Palette{scheme, depth: opt.colour_depth, trail: opt.trail});
+    let tui = |reseed| {
+        let mut tui = Tui::new(&opt.delay, opt.render, palette, reseed);
+        tui.save_to(&opt.save);
+        tui.set_stop_when_stable(opt.stop_when_stable);
+        tui
+    };
+    let interactive = opt.generations.is_none();
+    let life = |generation, rule: Option<Rule>| -> Box<dyn Automaton> {
+        if let Some(block) = opt.block.clone() {
+            return Box::new(BlockGame::new(generation, block));
         }
-        Subcommand::File {filename} => {
-            let (grid, r) = rle::read_file(&filename).unwrap();
-            generation = Generation::new(grid);
-            rule = r
+        let mut game = Game::new(generation, opt.rule.clone().or(rule));
+        if !chance.is_certain() {
+            game.set_chance(chance, seed);
         }
+        if interactive {
+            game.set_history_memory(opt.history_memory << 20);
+        }
+        if let Some(filename) = &opt.stats_out {
+            game.set_stats(StatsWriter::create(filename).unwrap_or_else(|err| exit_with(&err.to_string())));
+        }
+        Box::new(game)
+    };
+    if opt.stats_out.is_some() && (opt.block.is_some() || matches!(opt.cmd, Subcommand::Elementary {..} | Subcommand::Turmite {..})) {
+        exit_with("Statistics are only collected under life-like rules");
     }
-    if let Some(r) = opt.rule {
-        rule = Some(r);
+    let mut reseed: Option<Box<dyn FnMut() -> Generation>> = None;
+    let mut automaton: Box<dyn Automaton> = match opt.cmd {
+        Subcommand::Soup {width, height, density, fill, symmetry, soup_size} => {
+            let plain = soup_size.is_none() && symmetry.is_asymmetric();
+            let area = match plain {
+                true => (width, height),
+                false => symmetry.fit(soup_size.unwrap_or(16), width, height).unwrap_or_else(|err| exit_with(err)),
+            };
+            let density_mean = density.mean(area.0, area.1);
+            let mut soup: Box<dyn FnMut() -> Generation> = Box::new(move || match plain {
+                true => Generation::soup(&rng, width, height, &density, &fill),
+                false => Generation::symmetric_soup(&rng, width, height, &density, &fill, &symmetry, area),
+            });
+            let generation = soup();
+            let cells = area.0 * area.1;
+            eprintln!(
+                "Requested density {:.4}, filled {} of {} cells ({:.4})",
+                density_mean,
+                generation.population(),
+                cells,
+                generation.population() as f64 / cells as f64,
+            );
+            if let Some(name) = symmetry::of(&generation) {
+                eprintln!("Soup symmetry {}", name);
+            }
+            reseed = Some(soup);
+            life(generation, None)
+        }
+        Subcommand::File {filename} => {
+            let (grid, rule) = rle::read_file(&filename).unwrap_or_else(|err| exit_with(err));
+            life(Generation::new(grid), rule)
+        }
+        Subcommand::Analyze {filename, max_generations} => {
+            let (grid, rule) = rle::read_file(&filename).unwrap_or_else(|err| exit_with(err));
+            let rule = opt.rule.clone().or(rule);
+            let generation = Generation::new(grid);
+            let analysis = analysis::analyze(generation.grid.clone(), rule.clone(), max_generations);
+            print!("{}", analysis);
+            if let Some(name) = symmetry::of(&generation) {
+                println!("Symmetry {}", name);
+            }
+            let spacetime = analysis.cycle.filter(|cycle| cycle.period > 1)
+                .and_then(|cycle| symmetry::over_time(&generation, rule.clone(), &cycle));
+            if let Some(spacetime) = spacetime {
+                println!("Symmetry over time {}", spacetime);
+            }
+            if let Some(code) = apgcode::encode(&generation, rule.clone(), max_generations) {
+                println!("Apgcode {}", code);
+            }
+            if let Some(name) = Library::new().name(&generation, &rule.unwrap_or(Rule::default())) {
+                println!("This is a {}", name);
+            }
+            return;
+        }
+        Subcommand::Predecessor {filename, isolated} => {
+            if opt.block.is_some() {
+                exit_with("Predecessors can only be searched for under life-like rules");
+            }
+            let (grid, rule) = rle::read_file(&filename).unwrap_or_else(|err| exit_with(err));
+            let rule = opt.rule.clone().or(rule).unwrap_or(Rule::default());
+            match predecessor::predecessor(&Generation::new(grid), &rule, isolated) {
+                Some(parent) => match opt.output.as_deref() {
+                    Some(filename) => rle::write_file(filename, &parent.grid, Some(&rule.to_string())).unwrap_or_else(|err| exit_with(&err.to_string())),
+                    None => print!("{}", rle::encode(&parent.grid, Some(&rule.to_string()))),
+                },
+                None if isolated => println!("No pattern within reach of this one becomes exactly it"),
+                None => println!("No predecessor exists: this pattern is a Garden of Eden"),
+            }
+            return;
+        }
+        Subcommand::Search {soups, soup_size, density, fill, max_generations, format, rare} => {
+            if opt.block.is_some() {
+                exit_with("Soups can only be searched under life-like rules");
+            }
+            search(seed, soups, soup_size, &density, &fill, opt.rule.clone().unwrap_or(Rule::default()), max_generations, format, rare, opt.output.as_deref());
+            return;
+        }
+        Subcommand::Apgcode {code, width, height} => {
+            let pattern = apgcode::decode(&code).unwrap_or_else(|err| exit_with(err));
+            let (pattern_width, pattern_height) = (pattern.grid.first().map_or(0, |row| row.len()), pattern.grid.len());
+            if pattern_width > width || pattern_height > height {
+                exit_with("The pattern does not fit in the field");
+            }
+            let left = (width - pattern_width) / 2;
+            let top = (height - pattern_height) / 2;
+            life(pattern.crop(-(left as isize), -(top as isize), width, height), None)
+        }
+        Subcommand::Elementary {rule, width, height, density} => {
+            let row = match density {
+                Some(density) => {
+                    let mut soup: Box<dyn FnMut() -> Generation> = Box::new(move || Generation::new(vec![Elementary::soup(&rng, width, density)]));
+                    let row = soup().grid.remove(0);
+                    reseed = Some(soup);
+                    row
+                }
+                None => Elementary::single(width),
+            };
+            Box::new(Elementary::new(rule, row, height))
+        }
+        Subcommand::Turmite {rule, width, height, ants, moves} => {
+            let ants = Turmite::scatter(&rng, width, height, ants);
+            Box::new(Turmite::new(width, height, rule, ants, moves))
+        }
+    };
+    let escapes = (opt.escapes || opt.delete_escaping).then(|| match automaton.rule().parse::<Rule>() {
+        Ok(rule) => EscapeDetector::new(&rule, opt.delete_escaping),
+        Err(_) => exit_with("Escaping spaceships can only be tracked under life-like rules"),
+    });
+    match opt.generations {
+        Some(generations) => run_headless(automaton.as_mut(), generations, opt.stop_when_stable, opt.output.as_deref(), opt.objects, escapes),
+        None => {
+            let mut tui = tui(reseed);
+            if let Some(escapes) = escapes {
+                tui.track_escapes(escapes);
+            }
+            tui.run(automaton.as_mut()).unwrap()
+        }
     }
-    let mut game = Game::new(
-        generation,
-        rule,
-    );
-    game.run(&opt.delay);
 }
@@ -0,0 +1,212 @@
+use crate::automaton::Automaton;
+use crate::generation::Generation;
+use phf::{Map, phf_map};
+use std::fmt;
+use std::str::FromStr;
+
+static NAMED_BLOCK_RULES: Map<&'static str, &'static str> = phf_map! {
+    "bbm" => "MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15",
+    "billiard-ball" => "MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15",
+    "critters" => "MS,D15;14;13;3;11;5;6;1;7;9;10;2;12;4;8;0",
+    "tron" => "MS,D15;1;2;3;4;5;6;7;8;9;10;11;12;13;14;0",
+};
+
+/// A Margolus partitioning rule in MCell notation (`MS,D0;8;4;...`), giving
+/// the replacement for each of the 16 states of a 2x2 block. The cells of a
+/// block are numbered 1 2 / 4 8 from the top left.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BlockRule {
+    table: [u8; 16],
+}
+
+impl FromStr for BlockRule {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rulestring = s;
+        if NAMED_BLOCK_RULES.contains_key(rulestring) {
+            rulestring = NAMED_BLOCK_RULES[rulestring];
+        }
+        let entries = rulestring.strip_prefix("MS,D")
+            .ok_or("Block rules must start with MS,D")?;
+        let mut table = [0; 16];
+        let mut count = 0;
+        for entry in entries.split(';') {
+            if count == 16 {
+                return Err("Block rules need exactly 16 entries");
+            }
+            table[count] = match entry.trim().parse() {
+                Ok(value) if value < 16 => value,
+                _ => { return Err("Block rule entries must be numbers from 0 to 15"); }
+            };
+            count += 1;
+        }
+        if count != 16 {
+            return Err("Block rules need exactly 16 entries");
+        }
+        Ok(BlockRule{table})
+    }
+}
+
+impl fmt::Display for BlockRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries: Vec<String> = self.table.iter().map(|entry| entry.to_string()).collect();
+        write!(f, "MS,D{}", entries.join(";"))
+    }
+}
+
+impl BlockRule {
+    pub fn apply(&self, block: u8) -> u8 {
+        self.table[block as usize]
+    }
+}
+
+/// Runs a block rule over the grid, shifting the 2x2 partition by one cell
+/// diagonally on alternate generations. Cells beyond the edge count as dead.
+pub struct BlockGame {
+    current_generation: Generation,
+    rule: BlockRule,
+    offset: usize,
+    generation_count: u64,
+}
+
+impl BlockGame {
+    pub fn new(current_generation: Generation, rule: BlockRule) -> BlockGame {
+        BlockGame{current_generation, rule, offset: 0, generation_count: 0}
+    }
+
+    pub fn next_generation(&self) -> Generation {
+        let grid = &self.current_generation.grid;
+        let height = grid.len() as isize;
+        let width = grid[0].len() as isize;
+        let mut result = vec![vec![false; width as usize]; height as usize];
+        let start = -(self.offset as isize);
+        for top in (start..height).step_by(2) {
+            for left in (start..width).step_by(2) {
+                let cells = [(left, top), (left + 1, top), (left, top + 1), (left + 1, top + 1)];
+                let inside = |(x, y): (isize, isize)| x >= 0 && y >= 0 && x < width && y < height;
+                let mut block = 0;
+                for (bit, cell) in cells.iter().enumerate() {
+                    if inside(*cell) && grid[cell.1 as usize][cell.0 as usize] {
+                        block |= 1 << bit;
+                    }
+                }
+                let block = self.rule.apply(block);
+                for (bit, cell) in cells.iter().enumerate() {
+                    if inside(*cell) {
+                        result[cell.1 as usize][cell.0 as usize] = block >> bit & 1 == 1;
+                    }
+                }
+            }
+        }
+        Generation::new(result)
+    }
+}
+
+impl Automaton for BlockGame {
+    fn generation(&self) -> &Generation {
+        &self.current_generation
+    }
+
+    fn generation_count(&self) -> u64 {
+        self.generation_count
+    }
+
+    fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    fn step(&mut self) {
+        self.current_generation = self.next_generation();
+        self.offset = 1 - self.offset;
+        self.generation_count += 1;
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, alive: bool) {
+        self.current_generation.grid[y][x] = alive;
+    }
+
+    fn reset(&mut self, generation: Generation) {
+        self.current_generation = generation;
+        self.offset = 0;
+        self.generation_count = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    type Grid = Vec<Vec<bool>>;
+
+    #[rstest]
+    #[case("tron", [15, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 0])]
+    #[case("MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15", [0, 8, 4, 3, 2, 5, 9, 7, 1, 6, 10, 11, 12, 13, 14, 15])]
+    fn test_block_rule_from_str_ok(#[case] rulestring: &str, #[case] table: [u8; 16]) {
+        assert_eq!(Ok(BlockRule{table}), rulestring.parse());
+    }
+
+    #[rstest]
+    #[case("B3/S23", "Block rules must start with MS,D")]
+    #[case("MS,D0;1;2", "Block rules need exactly 16 entries")]
+    #[case("MS,D0;1;2;3;4;5;6;7;8;9;10;11;12;13;14;15;0", "Block rules need exactly 16 entries")]
+    #[case("MS,D0;1;2;3;4;5;6;7;8;9;10;11;12;13;14;16", "Block rule entries must be numbers from 0 to 15")]
+    fn test_block_rule_from_str_err(#[case] rulestring: &str, #[case] expected: &str) {
+        assert_eq!(Err(expected), rulestring.parse::<BlockRule>());
+    }
+
+    #[test]
+    fn test_block_rule_display() {
+        let rule: BlockRule = "critters".parse().unwrap();
+        assert_eq!(rule, rule.to_string().parse().unwrap());
+    }
+
+    #[rstest]
+    #[case( // a lone billiard ball moves diagonally, changing partition each step
+        "bbm",
+        vec![
+            vec![true, false, false, false],
+            vec![false, false, false, false],
+            vec![false, false, false, false],
+            vec![false, false, false, false],
+        ],
+        vec![
+            vec![false, false, false, false],
+            vec![false, true, false, false],
+            vec![false, false, false, false],
+            vec![false, false, false, false],
+        ],
+        vec![
+            vec![false, false, false, false],
+            vec![false, false, false, false],
+            vec![false, false, true, false],
+            vec![false, false, false, false],
+        ],
+    )]
+    #[case( // tron inverts empty blocks, with the cells past the edge dropped
+        "tron",
+        vec![
+            vec![false, false, false],
+            vec![false, false, false],
+            vec![false, false, false],
+        ],
+        vec![
+            vec![true, true, true],
+            vec![true, true, true],
+            vec![true, true, true],
+        ],
+        vec![
+            vec![true, true, true],
+            vec![true, false, false],
+            vec![true, false, false],
+        ],
+    )]
+    fn test_block_game_step(#[case] rule: BlockRule, #[case] grid: Grid, #[case] first: Grid, #[case] second: Grid) {
+        let mut game = BlockGame::new(Generation::new(grid), rule);
+        game.step();
+        assert_eq!(first, game.generation().grid);
+        game.step();
+        assert_eq!(second, game.generation().grid);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::automaton::Automaton;
+use crate::game::Game;
+use crate::generation::Generation;
+use crate::rule::Rule;
+
+pub type Cells = Vec<(usize, usize)>;
+
+// Cells further apart than this can't both neighbour a third cell, so can
+// never affect each other's next generation.
+const REACH: usize = 2;
+
+/// A separate object, cropped to its bounding box, whose top left cell was at
+/// `(x, y)` in the pattern it came from.
+#[derive(Debug, PartialEq)]
+pub struct Object {
+    pub x: usize,
+    pub y: usize,
+    pub generation: Generation,
+}
+
+fn find(parents: &mut Vec<usize>, index: usize) -> usize {
+    if parents[index] != index {
+        parents[index] = find(parents, parents[index]);
+    }
+    parents[index]
+}
+
+/// Groups cells into pieces, two cells being joined when one is in the
+/// other's neighbourhood.
+pub fn components(cells: &[Vec<bool>], neighbourhood: &[(i32, i32)]) -> Vec<Cells> {
+    let height = cells.len();
+    let width = cells.first().map_or(0, |row| row.len());
+    let mut seen = vec![vec![false; width]; height];
+    let mut pieces = Vec::new();
+    for (y, row) in cells.iter().enumerate() {
+        for (x, alive) in row.iter().enumerate() {
+            if !alive || seen[y][x] {
+                continue;
+            }
+            seen[y][x] = true;
+            let mut piece = vec![(x, y)];
+            let mut index = 0;
+            while index < piece.len() {
+                let (x, y) = piece[index];
+                for (dx, dy) in neighbourhood {
+                    let (nx, ny) = (x as isize + *dx as isize, y as isize + *dy as isize);
+                    if nx < 0 || ny < 0 || nx as usize >= width || ny as usize >= height {
+                        continue;
+                    }
+                    let (nx, ny) = (nx as usize, ny as usize);
+                    if cells[ny][nx] && !seen[ny][nx] {
+                        seen[ny][nx] = true;
+                        piece.push((nx, ny));
+                    }
+                }
+                index += 1;
+            }
+            pieces.push(piece);
+        }
+    }
+    pieces
+}
+
+fn near(a: &Cells, b: &Cells) -> bool {
+    a.iter().any(|(ax, ay)| b.iter().any(|(bx, by)| ax.abs_diff(*bx) <= REACH && ay.abs_diff(*by) <= REACH))
+}
+
+/// Runs `cells` for `period` generations in a box around them, big enough
+/// that they can't reach its edges, returning every generation.
+fn evolve(cells: &Cells, rule: &Option<Rule>, period: u64, bounds: (usize, usize, usize, usize)) -> Vec<Generation> {
+    let (left, top, width, height) = bounds;
+    let mut grid = vec![vec![false; width]; height];
+    for (x, y) in cells {
+        grid[y - top][x - left] = true;
+    }
+    let mut game = Game::new(Generation::new(grid), rule.clone());
+    (0..period).map(|_| {
+        game.step();
+        Generation::new(game.generation().grid.clone())
+    }).collect()
+}
+
+/// Whether two sets of cells evolve differently together than apart.
+fn interact(a: &Cells, b: &Cells, rule: &Option<Rule>, period: u64) -> bool {
+    let pad = period as usize + REACH;
+    let all: Cells = a.iter().chain(b).copied().collect();
+    let left = all.iter().map(|(x, _)| *x).min().unwrap();
+    let top = all.iter().map(|(_, y)| *y).min().unwrap();
+    let right = all.iter().map(|(x, _)| *x).max().unwrap();
+    let bottom = all.iter().map(|(_, y)| *y).max().unwrap();
+    let shift = |cells: &Cells| cells.iter().map(|(x, y)| (x + pad, y + pad)).collect::<Cells>();
+    let bounds = (left, top, right - left + 1 + 2 * pad, bottom - top + 1 + 2 * pad);
+    let (a, b, all) = (shift(a), shift(b), shift(&all));
+    let apart = (evolve(&a, rule, period, bounds), evolve(&b, rule, period, bounds));
+    let together = evolve(&all, rule, period, bounds);
+    together.iter().zip(apart.0.iter().zip(&apart.1)).any(|(joint, (a, b))| {
+        joint.grid.iter().zip(a.grid.iter().zip(&b.grid)).any(|(joint, (a, b))| {
+            joint.iter().zip(a.iter().zip(b)).any(|(joint, (a, b))| *joint != (*a || *b))
+        })
+    })
+}
+
+/// Splits a pattern repeating every `period` generations into its objects.
+/// Cells alive at any point of the period which touch through the rule's
+/// neighbourhood start out as one object, then neighbouring objects are
+/// merged when they don't evolve the same apart as together, so that a
+/// pond beside a block comes apart but a spark feeding a birth does not.
+pub fn separate(generation: &Generation, rule: Option<Rule>, period: u64) -> Vec<Object> {
+    let neighbourhood = rule.clone().unwrap_or(Rule::default()).neighbour_pattern.generate(1);
+    let period = period.max(1);
+    let mut ever_alive = generation.grid.clone();
+    let mut game = Game::new(Generation::new(generation.grid.clone()), rule.clone());
+    for _ in 1..period {
+        game.step();
+        for (ever, now) in ever_alive.iter_mut().zip(&game.generation().grid) {
+            for (ever, alive) in ever.iter_mut().zip(now) {
+                *ever |= *alive;
+            }
+        }
+    }
+    let pieces: Vec<Cells> = components(&ever_alive, &neighbourhood).into_iter()
+        .map(|piece| piece.into_iter().filter(|(x, y)| generation.grid[*y][*x]).collect())
+        .filter(|piece: &Cells| !piece.is_empty())
+        .collect();
+    let mut parents: Vec<usize> = (0..pieces.len()).collect();
+    for a in 0..pieces.len() {
+        for b in a + 1..pieces.len() {
+            if find(&mut parents, a) != find(&mut parents, b)
+                && near(&pieces[a], &pieces[b])
+                && interact(&pieces[a], &pieces[b], &rule, period) {
+                let root = find(&mut parents, a);
+                parents[root] = find(&mut parents, b);
+            }
+        }
+    }
+    let mut groups: Vec<(usize, Cells)> = Vec::new();
+    for (index, piece) in pieces.into_iter().enumerate() {
+        let root = find(&mut parents, index);
+        match groups.iter_mut().find(|(group, _)| *group == root) {
+            Some((_, cells)) => cells.extend(piece),
+            None => groups.push((root, piece)),
+        }
+    }
+    groups.into_iter().map(|(_, cells)| {
+        let x = cells.iter().map(|(x, _)| *x).min().unwrap();
+        let y = cells.iter().map(|(_, y)| *y).min().unwrap();
+        let width = cells.iter().map(|(cx, _)| cx - x + 1).max().unwrap();
+        let height = cells.iter().map(|(_, cy)| cy - y + 1).max().unwrap();
+        let mut grid = vec![vec![false; width]; height];
+        for (cx, cy) in cells {
+            grid[cy - y][cx - x] = true;
+        }
+        Object{x, y, generation: Generation::new(grid)}
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rle::pattern;
+
+    fn shapes(objects: &[Object]) -> Vec<(usize, usize, String)> {
+        objects.iter().map(|object| (object.x, object.y, object.generation.to_string())).collect()
+    }
+
+    #[test]
+    fn test_separate_pond_beside_block() {
+        let objects = separate(&pattern("x = 7, y = 4\nb2o$o2bob2o$o2bob2o$b2o!"), None, 1);
+        assert_eq!(2, objects.len());
+        assert_eq!(vec![(0, 0), (5, 1)], objects.iter().map(|object| (object.x, object.y)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_separate_oscillators() {
+        // A blinker, far from a beacon whose halves only touch at a corner.
+        let objects = separate(&pattern("x = 10, y = 4\n3o3b2o$6b2o$8b2o$8b2o!"), None, 2);
+        assert_eq!(
+            vec![
+                (0, 0, "▀▀▀".to_owned()),
+                (6, 0, "██  \n  ██".to_owned()),
+            ],
+            shapes(&objects),
+        );
+    }
+
+    #[test]
+    fn test_separate_merges_interacting_pieces() {
+        // Apart, the domino and the cell both die, together they give birth.
+        let objects = separate(&pattern("x = 3, y = 2\nobo$o!"), None, 1);
+        assert_eq!(1, objects.len());
+    }
+
+    #[test]
+    fn test_separate_empty() {
+        assert_eq!(Vec::<Object>::new(), separate(&pattern("x = 3, y = 3\n!"), None, 1));
+    }
+}
@@ -0,0 +1,133 @@
+use crate::generation::Generation;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Generation `start` recurs every `period` generations, moved by
+/// `displacement` cells each time.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Cycle {
+    pub start: u64,
+    pub period: u64,
+    pub displacement: (isize, isize),
+}
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "period {} from generation {}", self.period, self.start)?;
+        if self.displacement != (0, 0) {
+            write!(f, ", moving ({}, {})", self.displacement.0, self.displacement.1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Spots when a run starts repeating by remembering a hash of every
+/// generation, both where its cells are and of its shape alone, so that
+/// patterns which repeat further along are caught too. Once a cycle is
+/// found nothing more is remembered.
+pub struct PeriodDetector {
+    positions: HashMap<(u64, (isize, isize)), u64>,
+    shapes: HashMap<u64, (u64, (isize, isize))>,
+    cycle: Option<Cycle>,
+}
+
+impl PeriodDetector {
+    pub fn new() -> PeriodDetector {
+        PeriodDetector{positions: HashMap::new(), shapes: HashMap::new(), cycle: None}
+    }
+
+    pub fn cycle(&self) -> Option<Cycle> {
+        self.cycle
+    }
+
+    /// Records `generation` as generation number `count`, returning the
+    /// cycle once one has been found.
+    pub fn observe(&mut self, count: u64, generation: &Generation) -> Option<Cycle> {
+        self.observe_at(count, generation, (0, 0))
+    }
+
+    /// As `observe`, for a grid whose top left cell is at `origin`, for runs
+    /// which move the grid to follow the pattern.
+    pub fn observe_at(&mut self, count: u64, generation: &Generation, origin: (isize, isize)) -> Option<Cycle> {
+        if self.cycle.is_some() {
+            return self.cycle;
+        }
+        let position = (generation.position_hash(), origin);
+        if let Some(start) = self.positions.insert(position, count) {
+            self.cycle = Some(Cycle{start, period: count - start, displacement: (0, 0)});
+            return self.cycle;
+        }
+        let corner = generation.bounding_box().map_or((0, 0), |(x, y, _, _)| (x as isize + origin.0, y as isize + origin.1));
+        if let Some((start, (x, y))) = self.shapes.insert(generation.shape_hash(), (count, corner)) {
+            self.cycle = Some(Cycle{start, period: count - start, displacement: (corner.0 - x, corner.1 - y)});
+        }
+        self.cycle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::Automaton;
+    use crate::game::Game;
+    use rstest::rstest;
+
+    type Grid = Vec<Vec<bool>>;
+
+    fn run(grid: Grid, generations: u64) -> Option<Cycle> {
+        let mut game = Game::new(Generation::new(grid), None);
+        let mut detector = PeriodDetector::new();
+        detector.observe(0, game.generation());
+        for _ in 0..generations {
+            game.step();
+            detector.observe(game.generation_count(), game.generation());
+        }
+        detector.cycle()
+    }
+
+    fn glider() -> Grid {
+        let mut grid = vec![vec![false; 12]; 12];
+        for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            grid[y][x] = true;
+        }
+        grid
+    }
+
+    #[rstest]
+    #[case(vec![vec![false; 4]; 4], Some(Cycle{start: 0, period: 1, displacement: (0, 0)}))]
+    #[case(
+        vec![
+            vec![false, false, false, false, false],
+            vec![false, false, true, false, false],
+            vec![false, false, true, false, false],
+            vec![false, false, true, false, false],
+            vec![false, false, false, false, false],
+        ],
+        Some(Cycle{start: 0, period: 2, displacement: (0, 0)}),
+    )]
+    #[case(
+        vec![
+            vec![false, false, false, false],
+            vec![false, true, true, false],
+            vec![false, true, false, false],
+            vec![false, false, false, false],
+        ],
+        Some(Cycle{start: 1, period: 1, displacement: (0, 0)}),
+    )]
+    #[case(glider(), Some(Cycle{start: 0, period: 4, displacement: (1, 1)}))]
+    fn test_period_detector(#[case] grid: Grid, #[case] expected: Option<Cycle>) {
+        assert_eq!(expected, run(grid, 6));
+    }
+
+    #[test]
+    fn test_period_detector_waits_for_a_repeat() {
+        assert_eq!(None, run(glider(), 3));
+    }
+
+    #[rstest]
+    #[case(Cycle{start: 5, period: 2, displacement: (0, 0)}, "period 2 from generation 5")]
+    #[case(Cycle{start: 0, period: 4, displacement: (1, -1)}, "period 4 from generation 0, moving (1, -1)")]
+    fn test_cycle_display(#[case] cycle: Cycle, #[case] expected: &str) {
+        assert_eq!(expected, cycle.to_string());
+    }
+}
@@ -0,0 +1,196 @@
+use crate::generation::Generation;
+use crate::rule::Rule;
+
+/// A cell of the target, or around it, whose next state is fixed: it must
+/// become `alive` given the parent cell at `centre` and the live and
+/// undecided counts of its neighbours.
+struct Constraint {
+    alive: bool,
+    centre: usize,
+    live: u32,
+    unknown: u32,
+}
+
+/// Backtracking search for a parent, one cell at a time in reading order.
+/// Each constraint keeps count of its live and undecided neighbours, so a
+/// choice is undone as soon as some constraint can no longer be met.
+struct Search<'a> {
+    rule: &'a Rule,
+    cells: Vec<Option<bool>>,
+    constraints: Vec<Constraint>,
+    // The constraints each parent cell takes part in, as centre or neighbour.
+    touching: Vec<Vec<(usize, bool)>>,
+}
+
+impl Search<'_> {
+    fn feasible(&self, constraint: &Constraint) -> bool {
+        let centres = match self.cells[constraint.centre] {
+            Some(alive) => vec![alive],
+            None => vec![false, true],
+        };
+        let counts = constraint.live..=constraint.live + constraint.unknown;
+        centres.iter().any(|centre| counts.clone().any(|count| self.rule.apply(centre, &count) == constraint.alive))
+    }
+
+    fn set(&mut self, cell: usize, value: Option<bool>) {
+        let before = self.cells[cell];
+        self.cells[cell] = value;
+        for &(index, as_neighbour) in &self.touching[cell] {
+            if !as_neighbour {
+                continue;
+            }
+            let constraint = &mut self.constraints[index];
+            match (before, value) {
+                (None, Some(alive)) => {
+                    constraint.unknown -= 1;
+                    constraint.live += alive as u32;
+                }
+                (Some(alive), None) => {
+                    constraint.unknown += 1;
+                    constraint.live -= alive as u32;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn consistent(&self, cell: usize) -> bool {
+        self.touching[cell].iter().all(|(index, _)| self.feasible(&self.constraints[*index]))
+    }
+
+    fn run(&mut self) -> bool {
+        let mut cell = 0;
+        let mut tried: Vec<bool> = Vec::with_capacity(self.cells.len());
+        loop {
+            if cell == self.cells.len() {
+                return true;
+            }
+            // Try a dead cell first, then a live one, then backtrack.
+            let value = match tried.len() > cell {
+                false => {
+                    tried.push(false);
+                    false
+                }
+                true if !tried[cell] => {
+                    tried[cell] = true;
+                    self.set(cell, None);
+                    true
+                }
+                true => {
+                    tried.pop();
+                    self.set(cell, None);
+                    if cell == 0 {
+                        return false;
+                    }
+                    cell -= 1;
+                    continue;
+                }
+            };
+            self.set(cell, Some(value));
+            if self.consistent(cell) {
+                cell += 1;
+            }
+        }
+    }
+}
+
+/// Looks for a pattern which becomes `target` in one generation under
+/// `rule`. The parent may reach as far beyond the target as the rule's
+/// neighbourhood, and only the target's cells are constrained, so finding
+/// none proves the target is an orphan and any pattern holding it a Garden
+/// of Eden. If `isolated`, the cells around the target must also die, so that
+/// the parent on its own gives exactly the target.
+pub fn predecessor(target: &Generation, rule: &Rule, isolated: bool) -> Option<Generation> {
+    let neighbourhood = rule.neighbour_pattern.generate(1);
+    let reach = neighbourhood.iter().map(|(dx, dy)| dx.unsigned_abs().max(dy.unsigned_abs()) as usize).max().unwrap_or(0);
+    let (target_width, target_height) = (target.grid.first().map_or(0, |row| row.len()), target.grid.len());
+    let (width, height) = (target_width + 2 * reach, target_height + 2 * reach);
+    let mut constraints = Vec::new();
+    let mut touching = vec![Vec::new(); width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let inside = (reach..reach + target_width).contains(&x) && (reach..reach + target_height).contains(&y);
+            if !inside && !isolated {
+                continue;
+            }
+            let neighbours: Vec<usize> = neighbourhood.iter()
+                .map(|(dx, dy)| (x as isize + *dx as isize, y as isize + *dy as isize))
+                .filter(|(nx, ny)| (0..width as isize).contains(nx) && (0..height as isize).contains(ny))
+                .map(|(nx, ny)| ny as usize * width + nx as usize)
+                .collect();
+            let alive = inside && target.grid[y - reach][x - reach];
+            let centre = y * width + x;
+            touching[centre].push((constraints.len(), false));
+            for neighbour in &neighbours {
+                touching[*neighbour].push((constraints.len(), true));
+            }
+            constraints.push(Constraint{alive, centre, live: 0, unknown: neighbours.len() as u32});
+        }
+    }
+    let mut search = Search{rule, cells: vec![None; width * height], constraints, touching};
+    if !search.run() {
+        return None;
+    }
+    let cells: Vec<bool> = search.cells.iter().map(|cell| cell.unwrap()).collect();
+    Some(Generation::new(cells.chunks(width).map(|row| row.to_vec()).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rle::pattern;
+    use crate::automaton::Automaton;
+    use crate::game::Game;
+    use rstest::rstest;
+
+    /// The parent's next generation, with the one cell border around the
+    /// target cropped off unless `isolated`.
+    fn child(parent: Generation, rule: &Rule, isolated: bool) -> Vec<Vec<bool>> {
+        let (width, height) = (parent.grid[0].len(), parent.grid.len());
+        let mut game = Game::new(parent, Some(rule.clone()));
+        game.step();
+        match isolated {
+            true => game.generation().grid.clone(),
+            false => game.generation().crop(1, 1, width - 2, height - 2).grid,
+        }
+    }
+
+    #[rstest]
+    #[case("x = 3, y = 3\nbo$2bo$3o!", false)]
+    #[case("x = 3, y = 3\nbo$2bo$3o!", true)]
+    #[case("x = 4, y = 4\nb2o$o2bo$o2bo$b2o!", true)]
+    #[case("x = 5, y = 3\n5o$obobo$5o!", false)]
+    fn test_predecessor_found(#[case] rle: &str, #[case] isolated: bool) {
+        let target = pattern(rle);
+        let rule = Rule::default();
+        let parent = predecessor(&target, &rule, isolated).unwrap();
+        let expected = match isolated {
+            true => target.crop(-1, -1, target.grid[0].len() + 2, target.grid.len() + 2).grid,
+            false => target.grid,
+        };
+        assert_eq!(expected, child(parent, &rule, isolated));
+    }
+
+    #[test]
+    fn test_predecessor_none_where_nothing_lives_on() {
+        let rule = "B/S".parse().unwrap();
+        assert_eq!(None, predecessor(&pattern("x = 3, y = 1\nobo!"), &rule, false));
+        assert!(predecessor(&pattern("x = 3, y = 1\n!"), &rule, false).is_some());
+    }
+
+    #[test]
+    fn test_predecessor_isolated_cell() {
+        // A diagonal of three cells, for one, leaves only its middle.
+        let target = pattern("x = 1, y = 1\no!");
+        let parent = predecessor(&target, &Rule::default(), true).unwrap();
+        assert_eq!(target.crop(-1, -1, 3, 3).grid, child(parent, &Rule::default(), true));
+    }
+
+    #[test]
+    fn test_predecessor_von_neumann() {
+        let rule: Rule = "B1/S1V".parse().unwrap();
+        let target = pattern("x = 3, y = 3\nbo$obo$bo!");
+        let parent = predecessor(&target, &rule, false).unwrap();
+        assert_eq!(target.grid, child(parent, &rule, false));
+    }
+}
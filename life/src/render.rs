@@ -0,0 +1,199 @@
+use crate::generation::Generation;
+use std::str::FromStr;
+
+/// How cells are drawn as text: half blocks fit 1x2 cells in a character,
+/// braille 2x4 and plain ASCII one.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Renderer {
+    HalfBlock,
+    Braille,
+    Ascii,
+}
+
+impl FromStr for Renderer {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "half-block" => Ok(Renderer::HalfBlock),
+            "braille" => Ok(Renderer::Braille),
+            "ascii" => Ok(Renderer::Ascii),
+            _ => Err("Renderer must be one of half-block, braille or ascii"),
+        }
+    }
+}
+
+pub type Rgb = (u8, u8, u8);
+
+const WHITE: Rgb = (255, 255, 255);
+
+/// A character on screen, with the colours of its foreground and background
+/// (`None` leaves the terminal's own colour).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Glyph {
+    pub ch: char,
+    pub fg: Option<Rgb>,
+    pub bg: Option<Rgb>,
+}
+
+// Braille dots are numbered down the left column then the right, with the
+// bottom row added later as dots 7 and 8.
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+impl Renderer {
+    /// Width and height in cells of the block drawn by one character.
+    pub fn cell_size(&self) -> (usize, usize) {
+        match self {
+            Self::HalfBlock => (1, 2),
+            Self::Braille => (2, 4),
+            Self::Ascii => (1, 1),
+        }
+    }
+
+    /// Draws a block of pixels, each either blank or lit in some colour.
+    /// Half blocks can show two colours, the others show their brightest.
+    fn glyph(&self, block: &[Vec<Option<Rgb>>]) -> Glyph {
+        let brightest = || block.iter().flatten().flatten()
+            .max_by_key(|(r, g, b)| *r as u32 + *g as u32 + *b as u32)
+            .copied();
+        match self {
+            Self::HalfBlock => match (block[0][0], block[1][0]) {
+                (Some(upper), Some(lower)) if upper == lower => Glyph{ch: '█', fg: Some(upper), bg: None},
+                (Some(upper), lower) => Glyph{ch: '▀', fg: Some(upper), bg: lower},
+                (None, Some(lower)) => Glyph{ch: '▄', fg: Some(lower), bg: None},
+                (None, None) => Glyph{ch: ' ', fg: None, bg: None},
+            },
+            Self::Braille => {
+                let mut dots = 0;
+                for (y, row) in block.iter().enumerate() {
+                    for (x, cell) in row.iter().enumerate() {
+                        if cell.is_some() {
+                            dots |= BRAILLE_DOTS[y][x];
+                        }
+                    }
+                }
+                Glyph{ch: char::from_u32(0x2800 + dots).unwrap(), fg: brightest(), bg: None}
+            }
+            Self::Ascii => match block[0][0] {
+                Some(colour) => Glyph{ch: '#', fg: Some(colour), bg: None},
+                None => Glyph{ch: '.', fg: None, bg: None},
+            },
+        }
+    }
+
+    pub fn glyphs(&self, pixels: &[Vec<Option<Rgb>>]) -> Vec<Vec<Glyph>> {
+        let (width, height) = self.cell_size();
+        let pixels_width = pixels[0].len();
+        let mut lines = Vec::new();
+        for rows in pixels.chunks(height) {
+            let mut line = Vec::new();
+            for left in (0..pixels_width).step_by(width) {
+                let block: Vec<Vec<Option<Rgb>>> = (0..height).map(|y| {
+                    (left..left + width).map(|x| rows.get(y).and_then(|row| row.get(x)).copied().flatten()).collect()
+                }).collect();
+                line.push(self.glyph(&block));
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    pub fn render(&self, generation: &Generation) -> String {
+        let pixels: Vec<Vec<Option<Rgb>>> = generation.grid.iter()
+            .map(|row| row.iter().map(|alive| alive.then_some(WHITE)).collect())
+            .collect();
+        let lines: Vec<String> = self.glyphs(&pixels).iter()
+            .map(|line| line.iter().map(|glyph| glyph.ch).collect())
+            .collect();
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    type Grid = Vec<Vec<bool>>;
+
+    #[rstest]
+    #[case(
+        Renderer::Braille,
+        vec![
+            vec![false, true, false],
+            vec![false, false, true],
+            vec![true, true, true],
+        ],
+        "⠬⠆"
+    )]
+    #[case(
+        Renderer::Braille,
+        vec![
+            vec![true, true],
+            vec![true, true],
+            vec![true, true],
+            vec![true, true],
+            vec![false, true],
+        ],
+        "⣿\n⠈"
+    )]
+    #[case(
+        Renderer::Ascii,
+        vec![
+            vec![false, true, false],
+            vec![false, false, true],
+            vec![true, true, true],
+        ],
+        ".#.\n..#\n###"
+    )]
+    #[case(
+        Renderer::HalfBlock,
+        vec![
+            vec![false, true, false],
+            vec![false, false, true],
+            vec![true, true, true],
+        ],
+        " ▀▄\n▀▀▀"
+    )]
+    fn test_renderer_render(#[case] renderer: Renderer, #[case] grid: Grid, #[case] expected: &str) {
+        assert_eq!(expected, renderer.render(&Generation::new(grid)));
+    }
+
+    const RED: Rgb = (255, 0, 0);
+    const BLUE: Rgb = (0, 0, 255);
+
+    #[rstest]
+    #[case(
+        Renderer::HalfBlock,
+        vec![
+            vec![Some(RED), None, Some(RED)],
+            vec![Some(BLUE), Some(BLUE), Some(RED)],
+        ],
+        vec![
+            Glyph{ch: '▀', fg: Some(RED), bg: Some(BLUE)},
+            Glyph{ch: '▄', fg: Some(BLUE), bg: None},
+            Glyph{ch: '█', fg: Some(RED), bg: None},
+        ],
+    )]
+    #[case(
+        Renderer::Braille,
+        vec![
+            vec![Some(BLUE), Some(WHITE), None],
+            vec![Some(RED), None, None],
+        ],
+        vec![
+            Glyph{ch: '⠋', fg: Some(WHITE), bg: None},
+            Glyph{ch: '⠀', fg: None, bg: None},
+        ],
+    )]
+    fn test_renderer_glyphs(#[case] renderer: Renderer, #[case] pixels: Vec<Vec<Option<Rgb>>>, #[case] expected: Vec<Glyph>) {
+        assert_eq!(vec![expected], renderer.glyphs(&pixels));
+    }
+
+    #[rstest]
+    #[case("braille", Ok(Renderer::Braille))]
+    #[case("unicode", Err("Renderer must be one of half-block, braille or ascii"))]
+    fn test_renderer_from_str(#[case] renderer: &str, #[case] expected: Result<Renderer, &'static str>) {
+        assert_eq!(expected, renderer.parse());
+    }
+}
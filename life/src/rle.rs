@@ -1,6 +1,8 @@
 use std::fs;
 use std::num::ParseIntError;
 use crate::rule::Rule;
+#[cfg(test)]
+use crate::generation::Generation;
 
 type Grid = Vec<Vec<bool>>;
 
@@ -11,28 +13,35 @@ struct Header {
     rule: Option<Rule>,
 }
 
-fn parse_header(header: &str) -> Result<Header, ParseIntError> {
+/// Reads the `x`, `y` and `rule` fields of a header. The rule runs to the end
+/// of the line, as rules for other kinds of automaton can hold commas.
+fn parse_header(header: &str) -> Result<Header, &'static str> {
     let mut result = Header{
         width: 0,
         height: 0,
         rule: None,
     };
-    for row in header.split(',') {
-        let (name, value) = row.split_once('=').unwrap();
+    let mut rest = header;
+    while !rest.trim().is_empty() {
+        let (field, tail) = rest.split_once(',').unwrap_or((rest, ""));
+        let (name, value) = field.split_once('=').ok_or("An RLE header field needs a name and a value")?;
         match name.trim() {
             "x" => {
-                result.width = value.trim().parse()?;
+                result.width = value.trim().parse().map_err(|_| "An RLE header needs a number for x")?;
             }
             "y" => {
-                result.height = value.trim().parse()?;
+                result.height = value.trim().parse().map_err(|_| "An RLE header needs a number for y")?;
             }
             "rule" => {
-                result.rule = value.trim().parse().ok();
+                let (_, rule) = rest.split_once('=').unwrap();
+                result.rule = rule.trim().parse().ok();
+                break;
             }
             _ => {}
         }
+        rest = tail;
     }
-    return Ok(result);
+    Ok(result)
 }
 
 fn parse_grid(header: &Header, contents: &str) -> Result<Grid, ParseIntError> {
@@ -65,41 +74,136 @@ fn parse_grid(header: &Header, contents: &str) -> Result<Grid, ParseIntError> {
                 accumulator.push(ch)
             }
             '$' => {
+                let mut len = 1;
+                if !accumulator.is_empty() {
+                    len = accumulator.parse()?;
+                }
                 x = 0;
-                y += 1;
+                y += len;
+                accumulator = "".to_owned();
             }
             _ => {}
         }
     }
-    return Ok(grid);
+    Ok(grid)
+}
+
+/// Skips the `#` comment lines which can come before the header.
+fn skip_comments(contents: &str) -> &str {
+    let mut contents = contents.trim_start();
+    while contents.starts_with('#') {
+        contents = contents.split_once('\n').map_or("", |(_, rest)| rest).trim_start();
+    }
+    contents
+}
+
+/// The pattern's name, from a `#N` comment line.
+pub fn name(contents: &str) -> Option<&str> {
+    contents.lines()
+        .take_while(|line| line.trim_start().starts_with('#'))
+        .find_map(|line| line.trim_start().strip_prefix("#N"))
+        .map(|name| name.trim())
 }
 
-pub fn parse_file(contents: &str) -> Result<(Grid, Option<Rule>), ParseIntError> {
-    let (header, contents) = contents.split_once("\n").unwrap();
-    let header = parse_header(&header)?;
-    let grid = parse_grid(&header, &contents)?;
+pub fn parse_file(contents: &str) -> Result<(Grid, Option<Rule>), &'static str> {
+    let (header, contents) = skip_comments(contents).split_once("\n").ok_or("An RLE file needs a header line")?;
+    let header = parse_header(header)?;
+    let grid = parse_grid(&header, contents).map_err(|_| "Invalid run length in RLE file")?;
     Ok((grid, header.rule))
 }
 
-pub fn read_file(filename: &str) -> Result<(Grid, Option<Rule>), ParseIntError> {
+pub fn read_file(filename: &str) -> Result<(Grid, Option<Rule>), &'static str> {
     let contents = fs::read_to_string(filename)
         .expect("Something went wrong reading the file");
-    Ok(parse_file(&contents)?)
+    parse_file(&contents)
+}
+
+fn push_run(lines: &mut Vec<String>, len: usize, tag: char) {
+    let mut run = tag.to_string();
+    if len > 1 {
+        run = format!("{}{}", len, tag);
+    }
+    if lines.last().unwrap().len() + run.len() > 70 {
+        lines.push("".to_owned());
+    }
+    lines.last_mut().unwrap().push_str(&run);
+}
+
+pub fn encode(grid: &Grid, rule: Option<&str>) -> String {
+    let height = grid.len();
+    let width = grid.first().map_or(0, |row| row.len());
+    let mut header = format!("x = {}, y = {}", width, height);
+    if let Some(rule) = rule {
+        header += &format!(", rule = {}", rule);
+    }
+    let mut lines = vec!["".to_owned()];
+    let mut started = false;
+    let mut blank_rows = 0;
+    for row in grid {
+        let mut runs: Vec<(usize, bool)> = Vec::new();
+        for cell in row {
+            match runs.last_mut() {
+                Some((len, alive)) if alive == cell => { *len += 1; }
+                _ => { runs.push((1, *cell)); }
+            }
+        }
+        if let Some((_, false)) = runs.last() {
+            runs.pop();
+        }
+        if runs.is_empty() {
+            blank_rows += 1;
+            continue;
+        }
+        if started {
+            push_run(&mut lines, blank_rows + 1, '$');
+        } else if blank_rows > 0 {
+            push_run(&mut lines, blank_rows, '$');
+        }
+        started = true;
+        blank_rows = 0;
+        for (len, alive) in runs {
+            push_run(&mut lines, len, if alive { 'o' } else { 'b' });
+        }
+    }
+    push_run(&mut lines, 1, '!');
+    format!("{}\n{}\n", header, lines.join("\n"))
+}
+
+pub fn write_file(filename: &str, grid: &Grid, rule: Option<&str>) -> std::io::Result<()> {
+    fs::write(filename, encode(grid, rule))
+}
+
+/// The pattern in RLE `contents`, for tests.
+#[cfg(test)]
+pub fn pattern(contents: &str) -> Generation {
+    Generation::new(parse_file(contents).unwrap().0)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::margolus::BlockRule;
+    use crate::turmite::TurmiteRule;
     use rstest::rstest;
 
     #[rstest]
     #[case("x = 20, y = 10", Header{width: 20, height: 10, rule: None})]
     #[case("x = 20, y = 10, rule = B3/S23", Header{width: 20, height: 10, rule: "B3/S23".parse().ok()})]
+    #[case("x = 20, y = 10, rule = MS,D15;14;13;3;11;5;6;1;7;9;10;2;12;4;8;0", Header{width: 20, height: 10, rule: None})]
+    #[case("x = 4, y = 2, rule = {{{1,8,1},{1,2,1}}}", Header{width: 4, height: 2, rule: None})]
     fn test_parse_headers(#[case] header: &str, #[case] expected: Header) {
         let actual = parse_header(header).unwrap();
         assert_eq!(expected, actual);
     }
 
+    #[rstest]
+    #[case("x = 20, y", "An RLE header field needs a name and a value")]
+    #[case("x = 20, y = ten", "An RLE header needs a number for y")]
+    #[case("x = -1, y = 10", "An RLE header needs a number for x")]
+    fn test_parse_headers_err(#[case] header: &str, #[case] expected: &str) {
+        assert_eq!(Err(expected), parse_header(header));
+    }
+
     #[rstest]
     #[case(
         Header{width: 3, height: 3, rule: None},
@@ -110,6 +214,16 @@ mod tests {
             vec![true, true, true],
         ]
     )]
+    #[case(
+        Header{width: 2, height: 4, rule: None},
+        "o2$bo!",
+        vec![
+            vec![true, false],
+            vec![false, false],
+            vec![false, true],
+            vec![false, false],
+        ]
+    )]
     fn test_parse_grid(#[case] header: Header, #[case] contents: &str, #[case] expected: Grid) {
         let actual = parse_grid(&header, contents).unwrap();
         assert_eq!(expected, actual);
@@ -146,4 +260,54 @@ mod tests {
         let actual = parse_file(contents).unwrap();
         assert_eq!(expected, actual);
     }
+
+    #[rstest]
+    #[case(
+        vec![
+            vec![false, true, false],
+            vec![false, false, true],
+            vec![true, true, true],
+        ],
+        Some("B3/S23"),
+        "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n"
+    )]
+    #[case(
+        vec![
+            vec![false, false],
+            vec![true, false],
+            vec![false, false],
+            vec![false, false],
+            vec![false, true],
+        ],
+        None,
+        "x = 2, y = 5\n$o3$bo!\n"
+    )]
+    fn test_encode(#[case] grid: Grid, #[case] rule: Option<&str>, #[case] expected: &str) {
+        assert_eq!(expected, encode(&grid, rule));
+    }
+
+    #[test]
+    fn test_parse_file_with_comments() {
+        let contents = "#N Glider\n#C The smallest spaceship.\nx = 3, y = 3\nbo$2bo$3o!";
+        let (grid, _) = parse_file(contents).unwrap();
+        assert_eq!(vec![false, true, false], grid[0]);
+        assert_eq!(Some("Glider"), name(contents));
+        assert_eq!(None, name("x = 3, y = 3\nbo$2bo$3o!"));
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let (grid, rule) = read_file("data/glidergun.rle").unwrap();
+        let contents = encode(&grid, Some("B3/S23"));
+        assert!(contents.lines().all(|line| line.len() <= 70));
+        assert_eq!((grid, rule), parse_file(&contents).unwrap());
+    }
+
+    #[rstest]
+    #[case("critters".parse::<BlockRule>().unwrap().to_string())]
+    #[case("fibonacci".parse::<TurmiteRule>().unwrap().to_string())]
+    fn test_encode_round_trip_other_rules(#[case] rule: String) {
+        let grid = vec![vec![true, false, true], vec![false, true, true]];
+        assert_eq!((grid.clone(), None), parse_file(&encode(&grid, Some(&rule))).unwrap());
+    }
 }
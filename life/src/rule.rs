@@ -1,5 +1,6 @@
 use std::collections::HashSet;
 use phf::{Map, phf_map};
+use std::fmt;
 use std::str::FromStr;
 
 pub mod neighbourhood;
@@ -23,7 +24,7 @@ static NAMED_RULES: Map<&'static str, &'static str> = phf_map! {
     "folly" => "B1/S1V", // Named for u/FollyAdvice, as discovered at redd.it/6yenth
 };
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Rule {
     birth: HashSet<u32>,
     survival: HashSet<u32>,
@@ -60,13 +61,65 @@ impl FromStr for Rule {
     }
 }
 
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = |counts: &HashSet<u32>| {
+            let mut counts: Vec<&u32> = counts.iter().collect();
+            counts.sort();
+            counts.iter().map(|count| count.to_string()).collect::<String>()
+        };
+        write!(f, "B{}/S{}", digits(&self.birth), digits(&self.survival))?;
+        if self.neighbour_pattern == Pattern::VonNeumann {
+            write!(f, "V")?;
+        }
+        Ok(())
+    }
+}
+
 impl Rule {
     pub fn default() -> Rule {
         "original".parse().unwrap()
     }
 
     pub fn apply(&self, alive: &bool, neigbours: &u32) -> bool {
-        return (*alive && self.survival.contains(neigbours)) || (!*alive && self.birth.contains(neigbours));
+        (*alive && self.survival.contains(neigbours)) || (!*alive && self.birth.contains(neigbours))
+    }
+}
+
+pub fn parse_probability(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(p) if (0.0..=1.0).contains(&p) => Ok(p),
+        _ => Err(format!("{} is not a probability between 0 and 1", s)),
+    }
+}
+
+/// Probabilities applied on top of a rule: a birth or survival the rule allows
+/// only happens with the given chance, and any dead cell the rule leaves dead
+/// is born anyway with the noise chance.
+#[derive(Debug, PartialEq)]
+pub struct Chance {
+    pub birth: f64,
+    pub survival: f64,
+    pub noise: f64,
+}
+
+impl Chance {
+    pub fn certain() -> Chance {
+        Chance{birth: 1.0, survival: 1.0, noise: 0.0}
+    }
+
+    pub fn is_certain(&self) -> bool {
+        *self == Chance::certain()
+    }
+
+    pub fn apply(&self, rng: &fastrand::Rng, rule: &Rule, alive: &bool, neigbours: &u32) -> bool {
+        if rule.apply(alive, neigbours) {
+            let chance = if *alive { self.survival } else { self.birth };
+            if rng.f64() < chance {
+                return true;
+            }
+        }
+        !*alive && rng.f64() < self.noise
     }
 }
 
@@ -140,6 +193,15 @@ mod tests {
         assert_eq!(Err(expected), rule);
     }
 
+    #[rstest]
+    #[case("original", "B3/S23")]
+    #[case("23/36", "B36/S23")]
+    #[case("folly", "B1/S1V")]
+    #[case("seeds", "B2/S")]
+    fn test_rule_display(#[case] rulestring: &str, #[case] expected: &str) {
+        assert_eq!(expected, rulestring.parse::<Rule>().unwrap().to_string());
+    }
+
     #[rstest]
     #[case(false, 2, false)]
     #[case(false, 3, true)]
@@ -153,4 +215,37 @@ mod tests {
         let actual = rule.apply(&alive, &neigbours);
         assert_eq!(expected, actual);
     }
+
+    #[rstest]
+    #[case("0.5", Ok(0.5))]
+    #[case("1", Ok(1.0))]
+    #[case("1.5", Err("1.5 is not a probability between 0 and 1".to_owned()))]
+    #[case("half", Err("half is not a probability between 0 and 1".to_owned()))]
+    fn test_parse_probability(#[case] probability: &str, #[case] expected: Result<f64, String>) {
+        assert_eq!(expected, parse_probability(probability));
+    }
+
+    #[rstest]
+    #[case(Chance::certain(), false, 3, true)]
+    #[case(Chance::certain(), true, 4, false)]
+    #[case(Chance{birth: 0.0, survival: 1.0, noise: 0.0}, false, 3, false)]
+    #[case(Chance{birth: 1.0, survival: 0.0, noise: 0.0}, true, 2, false)]
+    #[case(Chance{birth: 0.0, survival: 0.0, noise: 1.0}, true, 4, false)]
+    #[case(Chance{birth: 0.0, survival: 0.0, noise: 1.0}, false, 4, true)]
+    fn test_chance_apply(#[case] chance: Chance, #[case] alive: bool, #[case] neigbours: u32, #[case] expected: bool) {
+        let rng = fastrand::Rng::with_seed(0);
+        let actual = chance.apply(&rng, &classic(), &alive, &neigbours);
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_chance_apply_is_seeded() {
+        let chance = Chance{birth: 0.5, survival: 0.5, noise: 0.1};
+        let run = |seed| {
+            let rng = fastrand::Rng::with_seed(seed);
+            (0..64).map(|n| chance.apply(&rng, &classic(), &(n % 2 == 0), &(n % 9))).collect::<Vec<bool>>()
+        };
+        assert_eq!(run(42), run(42));
+        assert_ne!(run(42), run(43));
+    }
 }
@@ -6,7 +6,7 @@ fn safe_push(vector: &mut Neighbourhood, x: i32, y: i32) {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Pattern {
     Moore,
     VonNeumann,
@@ -0,0 +1,78 @@
+use crate::render::Glyph;
+
+/// Changed glyphs to draw along a line, starting at column `x` of row `y`.
+#[derive(Debug, PartialEq)]
+pub struct Run {
+    pub x: u16,
+    pub y: u16,
+    pub glyphs: Vec<Glyph>,
+}
+
+/// The frame last drawn on the terminal, kept so that drawing the next one
+/// only sends the glyphs which changed.
+pub struct Screen {
+    lines: Vec<Vec<Glyph>>,
+}
+
+impl Screen {
+    pub fn new() -> Screen {
+        Screen{lines: Vec::new()}
+    }
+
+    /// Swaps in `frame` as the frame on screen, returning what has to be
+    /// drawn to get there. Everything is redrawn when the size changes.
+    pub fn update(&mut self, frame: Vec<Vec<Glyph>>) -> Vec<Run> {
+        let resized = frame.len() != self.lines.len()
+            || frame.iter().zip(&self.lines).any(|(new, old)| new.len() != old.len());
+        let mut runs = Vec::new();
+        for (y, line) in frame.iter().enumerate() {
+            let mut run: Option<Run> = None;
+            for (x, glyph) in line.iter().enumerate() {
+                if !resized && self.lines[y][x] == *glyph {
+                    runs.extend(run.take());
+                    continue;
+                }
+                run.get_or_insert(Run{x: x as u16, y: y as u16, glyphs: Vec::new()}).glyphs.push(*glyph);
+            }
+            runs.extend(run);
+        }
+        self.lines = frame;
+        runs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str) -> Vec<Glyph> {
+        text.chars().map(|ch| Glyph{ch, fg: None, bg: None}).collect()
+    }
+
+    #[test]
+    fn test_screen_update_draws_everything_first() {
+        let mut screen = Screen::new();
+        assert_eq!(
+            vec![Run{x: 0, y: 0, glyphs: line("ab")}, Run{x: 0, y: 1, glyphs: line("cd")}],
+            screen.update(vec![line("ab"), line("cd")]),
+        );
+    }
+
+    #[test]
+    fn test_screen_update_draws_changes() {
+        let mut screen = Screen::new();
+        screen.update(vec![line("abcde"), line("fghij")]);
+        assert_eq!(
+            vec![Run{x: 1, y: 0, glyphs: line("XY")}, Run{x: 4, y: 0, glyphs: line("Z")}],
+            screen.update(vec![line("aXYdZ"), line("fghij")]),
+        );
+        assert_eq!(Vec::<Run>::new(), screen.update(vec![line("aXYdZ"), line("fghij")]));
+    }
+
+    #[test]
+    fn test_screen_update_redraws_after_resize() {
+        let mut screen = Screen::new();
+        screen.update(vec![line("ab")]);
+        assert_eq!(vec![Run{x: 0, y: 0, glyphs: line("abc")}], screen.update(vec![line("abc")]));
+    }
+}
@@ -0,0 +1,222 @@
+use crate::generation::Generation;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/// How a generation changed from the one before it. Density is the share of
+/// live cells within the bounding box.
+#[derive(Debug, PartialEq)]
+pub struct Record {
+    pub generation: u64,
+    pub population: usize,
+    pub births: usize,
+    pub deaths: usize,
+    pub bounding_box: Option<(usize, usize, usize, usize)>,
+    pub density: f64,
+}
+
+impl Record {
+    /// The record for `next`, generation number `generation`, which followed
+    /// `previous`, or started the run if there is none.
+    pub fn between(generation: u64, previous: Option<&Generation>, next: &Generation) -> Record {
+        let (mut births, mut deaths) = (0, 0);
+        if let Some(previous) = previous {
+            for (before, after) in previous.grid.iter().flatten().zip(next.grid.iter().flatten()) {
+                match (before, after) {
+                    (false, true) => births += 1,
+                    (true, false) => deaths += 1,
+                    _ => {}
+                }
+            }
+        }
+        let population = next.population();
+        let bounding_box = next.bounding_box();
+        let density = bounding_box.map_or(0.0, |(_, _, width, height)| population as f64 / (width * height) as f64);
+        Record{generation, population, births, deaths, bounding_box, density}
+    }
+
+    fn csv(&self, run: u64) -> String {
+        let bounding_box = match self.bounding_box {
+            Some((left, top, width, height)) => format!("{},{},{},{}", left, top, width, height),
+            None => ",,,".to_owned(),
+        };
+        format!("{},{},{},{},{},{},{:.4}", run, self.generation, self.population, self.births, self.deaths, bounding_box, self.density)
+    }
+
+    fn json(&self, run: u64) -> String {
+        let bounding_box = match self.bounding_box {
+            Some((left, top, width, height)) => format!("[{}, {}, {}, {}]", left, top, width, height),
+            None => "null".to_owned(),
+        };
+        format!(
+            "{{\"run\": {}, \"generation\": {}, \"population\": {}, \"births\": {}, \"deaths\": {}, \"bounding_box\": {}, \"density\": {:.4}}}",
+            run,
+            self.generation,
+            self.population,
+            self.births,
+            self.deaths,
+            bounding_box,
+            self.density,
+        )
+    }
+}
+
+/// How records are laid out in the file.
+#[derive(Debug, PartialEq)]
+enum Layout {
+    Csv,
+    JsonLines,
+    JsonArray,
+}
+
+/// Writes a record for every generation, as CSV, as JSON Lines to files
+/// ending in `.jsonl` or as a JSON array to files ending in `.json`. Each
+/// generation is written once, the first time it is reached, so stepping
+/// forwards again after going back adds nothing. Records are numbered by
+/// run, starting again from 0 after each restart. The file is finished off
+/// when the writer is dropped, and an error doing so is reported on stderr.
+pub struct StatsWriter {
+    out: Box<dyn Write>,
+    layout: Layout,
+    run: u64,
+    written: Option<u64>,
+    records: u64,
+    failed: bool,
+}
+
+impl StatsWriter {
+    pub fn create(filename: &str) -> io::Result<StatsWriter> {
+        let layout = match filename {
+            name if name.ends_with(".jsonl") => Layout::JsonLines,
+            name if name.ends_with(".json") => Layout::JsonArray,
+            _ => Layout::Csv,
+        };
+        let mut out: Box<dyn Write> = Box::new(BufWriter::new(File::create(filename)?));
+        if layout == Layout::Csv {
+            writeln!(out, "run,generation,population,births,deaths,left,top,width,height,density")?;
+        }
+        Ok(StatsWriter{out, layout, run: 0, written: None, records: 0, failed: false})
+    }
+
+    /// Writes `record` unless its generation has been written already.
+    pub fn write(&mut self, record: &Record) -> io::Result<()> {
+        if self.written.is_some_and(|written| record.generation <= written) {
+            return Ok(());
+        }
+        self.written = Some(record.generation);
+        let result = match self.layout {
+            Layout::Csv => writeln!(self.out, "{}", record.csv(self.run)),
+            Layout::JsonLines => writeln!(self.out, "{}", record.json(self.run)),
+            Layout::JsonArray => {
+                let separator = if self.records == 0 { "[" } else { "," };
+                write!(self.out, "{}\n  {}", separator, record.json(self.run))
+            }
+        };
+        self.records += 1;
+        self.failed |= result.is_err();
+        result
+    }
+
+    /// Starts a new run, as when the automaton is reseeded.
+    pub fn restart(&mut self) {
+        self.run += 1;
+        self.written = None;
+    }
+
+    /// Closes the JSON array, if there is one, and flushes the file.
+    fn finish(&mut self) -> io::Result<()> {
+        if self.layout == Layout::JsonArray {
+            match self.records {
+                0 => writeln!(self.out, "[]")?,
+                _ => writeln!(self.out, "\n]")?,
+            }
+        }
+        self.out.flush()
+    }
+}
+
+impl Drop for StatsWriter {
+    fn drop(&mut self) {
+        if self.failed {
+            return;
+        }
+        if let Err(err) = self.finish() {
+            eprintln!("Could not write statistics: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blinker(vertical: bool) -> Generation {
+        let grid = (0..5).map(|y| (0..5).map(|x| match vertical {
+            true => x == 2 && (1..4).contains(&y),
+            false => y == 2 && (1..4).contains(&x),
+        }).collect()).collect();
+        Generation::new(grid)
+    }
+
+    #[test]
+    fn test_record_between() {
+        let record = Record::between(1, Some(&blinker(false)), &blinker(true));
+        assert_eq!(Record{generation: 1, population: 3, births: 2, deaths: 2, bounding_box: Some((2, 1, 1, 3)), density: 1.0}, record);
+        assert_eq!("0,1,3,2,2,2,1,1,3,1.0000", record.csv(0));
+        assert_eq!(
+            "{\"run\": 0, \"generation\": 1, \"population\": 3, \"births\": 2, \"deaths\": 2, \"bounding_box\": [2, 1, 1, 3], \"density\": 1.0000}",
+            record.json(0),
+        );
+    }
+
+    #[test]
+    fn test_record_start_and_empty() {
+        let record = Record::between(0, None, &Generation::new(vec![vec![false; 3]; 3]));
+        assert_eq!("2,0,0,0,0,,,,,0.0000", record.csv(2));
+        assert!(record.json(2).contains("\"bounding_box\": null"));
+    }
+
+    fn write_records(filename: &str) -> String {
+        let filename = std::env::temp_dir().join(filename);
+        let filename = filename.to_str().unwrap();
+        {
+            let mut stats = StatsWriter::create(filename).unwrap();
+            stats.write(&Record::between(0, None, &blinker(false))).unwrap();
+            stats.write(&Record::between(1, Some(&blinker(false)), &blinker(true))).unwrap();
+        }
+        let contents = std::fs::read_to_string(filename).unwrap();
+        std::fs::remove_file(filename).unwrap();
+        contents
+    }
+
+    #[test]
+    fn test_stats_writer_layouts() {
+        let lines = write_records("life_test_stats_layout.jsonl");
+        assert_eq!(2, lines.lines().count());
+        assert!(lines.lines().all(|line| line.starts_with("{\"run\": 0") && line.ends_with('}')));
+        let array = write_records("life_test_stats_layout.json");
+        let rows: Vec<&str> = array.lines().collect();
+        assert_eq!(4, rows.len());
+        assert_eq!(("[", "]"), (rows[0], rows[3]));
+        assert!(rows[1].ends_with("},") && rows[2].ends_with('}'));
+        assert!(write_records("life_test_stats_layout.csv").starts_with("run,generation,"));
+    }
+
+    struct Broken;
+
+    impl Write for Broken {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::Error::other("disk full"))
+        }
+    }
+
+    #[test]
+    fn test_stats_writer_reports_failure() {
+        let mut stats = StatsWriter{out: Box::new(Broken), layout: Layout::Csv, run: 0, written: None, records: 0, failed: false};
+        assert!(stats.write(&Record::between(0, None, &blinker(false))).is_err());
+        assert!(stats.failed);
+    }
+}
@@ -0,0 +1,323 @@
+use crate::apgcode;
+use crate::generation::Generation;
+use crate::period::Cycle;
+use crate::rule::Rule;
+use phf::{Map, phf_map};
+use std::fmt;
+use std::str::FromStr;
+
+type Grid = Vec<Vec<bool>>;
+
+/// A rotation or reflection of a `width` by `height` box onto itself. The
+/// quarter turn and diagonal reflections only make sense for square boxes.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Transform {
+    Rotate90,
+    Rotate180,
+    FlipX,
+    FlipY,
+    Transpose,
+    AntiTranspose,
+}
+
+impl Transform {
+    pub fn apply(&self, x: usize, y: usize, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            Self::Rotate90 => (width - 1 - y, x),
+            Self::Rotate180 => (width - 1 - x, height - 1 - y),
+            Self::FlipX => (width - 1 - x, y),
+            Self::FlipY => (x, height - 1 - y),
+            Self::Transpose => (y, x),
+            Self::AntiTranspose => (width - 1 - y, height - 1 - x),
+        }
+    }
+}
+
+/// How a soup of size `n` extends along one axis: left as `n` cells, or
+/// mirrored out to `2n-1` cells (sharing the middle) or `2n` cells.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Span {
+    Single,
+    Odd,
+    Even,
+}
+
+impl Span {
+    fn length(&self, size: usize) -> usize {
+        match self {
+            Self::Single => size,
+            Self::Odd => 2 * size - 1,
+            Self::Even => 2 * size,
+        }
+    }
+}
+
+use Span::{Even, Odd, Single};
+use Transform::{AntiTranspose, FlipX, FlipY, Rotate180, Rotate90, Transpose};
+
+type Group = (&'static [Transform], Span, Span);
+
+// Named as in apgsearch, where the suffix gives the centre of symmetry:
+// 1 for a cell, 2 for the edge between two cells and 4 for a corner.
+static SYMMETRIES: Map<&'static str, Group> = phf_map! {
+    "C1" => (&[], Single, Single),
+    "C2_1" => (&[Rotate180], Odd, Odd),
+    "C2_2" => (&[Rotate180], Even, Odd),
+    "C2_4" => (&[Rotate180], Even, Even),
+    "C4_1" => (&[Rotate90], Odd, Odd),
+    "C4_4" => (&[Rotate90], Even, Even),
+    "D2_+1" => (&[FlipX], Odd, Single),
+    "D2_+2" => (&[FlipX], Even, Single),
+    "D2_x" => (&[Transpose], Single, Single),
+    "D4_+1" => (&[FlipX, FlipY], Odd, Odd),
+    "D4_+2" => (&[FlipX, FlipY], Even, Odd),
+    "D4_+4" => (&[FlipX, FlipY], Even, Even),
+    "D4_x1" => (&[Transpose, AntiTranspose], Odd, Odd),
+    "D4_x4" => (&[Transpose, AntiTranspose], Even, Even),
+    "D8_1" => (&[Rotate90, FlipX], Odd, Odd),
+    "D8_4" => (&[Rotate90, FlipX], Even, Even),
+};
+
+#[derive(Debug, PartialEq)]
+pub struct Symmetry {
+    generators: &'static [Transform],
+    spans: (Span, Span),
+}
+
+impl FromStr for Symmetry {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match SYMMETRIES.get(s) {
+            Some((generators, x, y)) => Ok(Symmetry{generators, spans: (*x, *y)}),
+            None => Err("Unrecognised symmetry, expected one like C1, C2_4, D4_x1 or D8_1"),
+        }
+    }
+}
+
+impl Symmetry {
+    pub fn is_asymmetric(&self) -> bool {
+        self.generators.is_empty()
+    }
+
+    /// Width and height of a symmetric soup grown from a `size` square.
+    pub fn dimensions(&self, size: usize) -> (usize, usize) {
+        (self.spans.0.length(size), self.spans.1.length(size))
+    }
+
+    /// The dimensions of a soup grown from a `size` square, if it fits in a
+    /// `width` by `height` field.
+    pub fn fit(&self, size: usize, width: usize, height: usize) -> Result<(usize, usize), &'static str> {
+        if size == 0 {
+            return Err("A soup must be at least 1 cell across");
+        }
+        let (soup_width, soup_height) = self.dimensions(size);
+        if soup_width > width || soup_height > height {
+            return Err("The soup does not fit in the field");
+        }
+        Ok((soup_width, soup_height))
+    }
+
+    /// The first cell, in reading order, that the symmetry maps `(x, y)` onto.
+    pub fn representative(&self, x: usize, y: usize, width: usize, height: usize) -> (usize, usize) {
+        let mut orbit = vec![(x, y)];
+        let mut index = 0;
+        while index < orbit.len() {
+            let (x, y) = orbit[index];
+            for transform in self.generators {
+                let image = transform.apply(x, y, width, height);
+                if !orbit.contains(&image) {
+                    orbit.push(image);
+                }
+            }
+            index += 1;
+        }
+        orbit.into_iter().min_by_key(|(x, y)| (*y, *x)).unwrap()
+    }
+}
+
+// Orientations as `apgcode::orientations` lists them: the identity, the
+// reflection in the vertical axis, a quarter turn, the reflection in the
+// leading diagonal, a half turn, the reflection in the horizontal axis, three
+// quarter turns and the reflection in the other diagonal.
+const FLIP_X: usize = 1;
+const QUARTER_TURN: usize = 2;
+const TRANSPOSE: usize = 3;
+const HALF_TURN: usize = 4;
+const FLIP_Y: usize = 5;
+const ANTI_TRANSPOSE: usize = 7;
+const REFLECTIONS: [usize; 4] = [FLIP_X, TRANSPOSE, FLIP_Y, ANTI_TRANSPOSE];
+
+/// The live cells of `generation` cropped to their bounding box.
+fn shape(generation: &Generation) -> Option<Grid> {
+    let (left, top, width, height) = generation.bounding_box()?;
+    Some(generation.crop(left as isize, top as isize, width, height).grid)
+}
+
+/// The orientations of `shape` which give `other`.
+fn orientations_onto(shape: &Grid, other: &Grid) -> Vec<usize> {
+    apgcode::orientations(shape).iter().enumerate()
+        .filter(|(_, orientation)| *orientation == other)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Names a group of orientations, leaving out where its centre is.
+fn group(orientations: &[usize]) -> &'static str {
+    let has = |orientation| orientations.contains(&orientation);
+    match orientations.len() {
+        8 => "D8",
+        4 if has(QUARTER_TURN) => "C4",
+        4 if has(FLIP_X) => "D4_+",
+        4 => "D4_x",
+        2 if has(HALF_TURN) => "C2",
+        2 if has(FLIP_X) || has(FLIP_Y) => "D2_+",
+        2 => "D2_x",
+        _ => "C1",
+    }
+}
+
+/// The symmetry of the live cells of `generation`, named as soup symmetries
+/// are, such as C2_4 or D4_+1, or `None` if there are none.
+pub fn of(generation: &Generation) -> Option<String> {
+    let shape = shape(generation)?;
+    let (odd_width, odd_height) = (shape[0].len() % 2 == 1, shape.len() % 2 == 1);
+    let orientations = orientations_onto(&shape, &shape);
+    let centre = match (odd_width, odd_height) {
+        (true, true) => "1",
+        (false, false) => "4",
+        _ => "2",
+    };
+    let name = match group(&orientations) {
+        "D2_+" => match orientations.contains(&FLIP_X) {
+            true => if odd_width { "D2_+1" } else { "D2_+2" },
+            false => if odd_height { "D2_+1" } else { "D2_+2" },
+        }.to_owned(),
+        "D4_+" => format!("D4_+{}", centre),
+        "D4_x" => format!("D4_x{}", centre),
+        name @ ("C1" | "D2_x") => name.to_owned(),
+        name => format!("{}_{}", name, centre),
+    };
+    Some(name)
+}
+
+/// The symmetry of a cycle as a whole, taking in phases which are rotations
+/// or reflections of the first. When the only symmetries found between
+/// phases are reflections and the pattern moves, as with the glider, they
+/// are glide reflections.
+#[derive(Debug, PartialEq)]
+pub struct Spacetime {
+    pub group: &'static str,
+    pub glide: bool,
+}
+
+impl fmt::Display for Spacetime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.group)?;
+        if self.glide {
+            write!(f, " with glide reflection")?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `generation` through `cycle`, finding which of its phases are the
+/// first rotated or reflected, wherever they have moved to.
+pub fn over_time(generation: &Generation, rule: Option<Rule>, cycle: &Cycle) -> Option<Spacetime> {
+    let shapes: Vec<Grid> = apgcode::phases(generation, rule, cycle).iter().filter_map(shape).collect();
+    let first = shapes.first()?;
+    let still = orientations_onto(first, first);
+    let mut orientations = still.clone();
+    for later in &shapes[1..] {
+        for orientation in orientations_onto(first, later) {
+            if !orientations.contains(&orientation) {
+                orientations.push(orientation);
+            }
+        }
+    }
+    let new: Vec<usize> = orientations.iter().copied().filter(|orientation| !still.contains(orientation)).collect();
+    let glide = cycle.displacement != (0, 0)
+        && !new.is_empty() && new.iter().all(|orientation| REFLECTIONS.contains(orientation));
+    Some(Spacetime{group: group(&orientations), glide})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rle::pattern;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("C1", 16, (16, 16))]
+    #[case("C2_1", 16, (31, 31))]
+    #[case("C2_2", 16, (32, 31))]
+    #[case("D2_+1", 16, (31, 16))]
+    #[case("D2_x", 16, (16, 16))]
+    #[case("D8_4", 16, (32, 32))]
+    fn test_symmetry_dimensions(#[case] symmetry: Symmetry, #[case] size: usize, #[case] expected: (usize, usize)) {
+        assert_eq!(expected, symmetry.dimensions(size));
+    }
+
+    #[rstest]
+    #[case("D8_4", 5, Ok((10, 10)))]
+    #[case("D8_4", 6, Err("The soup does not fit in the field"))]
+    #[case("C1", 0, Err("A soup must be at least 1 cell across"))]
+    fn test_symmetry_fit(#[case] symmetry: Symmetry, #[case] size: usize, #[case] expected: Result<(usize, usize), &str>) {
+        assert_eq!(expected, symmetry.fit(size, 10, 10));
+    }
+
+    #[test]
+    fn test_symmetry_from_str_err() {
+        assert_eq!(
+            Err("Unrecognised symmetry, expected one like C1, C2_4, D4_x1 or D8_1"),
+            "C3".parse::<Symmetry>(),
+        );
+    }
+
+    #[rstest]
+    #[case("C1", (3, 2), (3, 2))]
+    #[case("C2_4", (3, 2), (0, 1))]
+    #[case("C4_4", (3, 2), (2, 0))]
+    #[case("D2_+2", (3, 2), (0, 2))]
+    #[case("D2_x", (3, 2), (3, 2))]
+    #[case("D2_x", (2, 3), (3, 2))]
+    #[case("D8_4", (3, 2), (1, 0))]
+    #[case("D8_4", (2, 2), (1, 1))]
+    fn test_symmetry_representative(#[case] symmetry: Symmetry, #[case] cell: (usize, usize), #[case] expected: (usize, usize)) {
+        assert_eq!(expected, symmetry.representative(cell.0, cell.1, 4, 4));
+    }
+
+    #[rstest]
+    #[case("x = 2, y = 2\n2o$2o!", "D8_4")]
+    #[case("x = 3, y = 3\nobo$bo$obo!", "D8_1")]
+    #[case("x = 3, y = 1\n3o!", "D4_+1")]
+    #[case("x = 4, y = 3\nb2o$o2bo$b2o!", "D4_+2")]
+    #[case("x = 4, y = 4\nb2o$o2bo$o2bo$b2o!", "D8_4")]
+    #[case("x = 4, y = 4\n2o$2o$2b2o$2b2o!", "D4_x4")]
+    #[case("x = 3, y = 3\n2o$obo$bo!", "D2_x")]
+    #[case("x = 3, y = 2\nobo$3o!", "D2_+1")]
+    #[case("x = 2, y = 3\n2o$o$2o!", "D2_+1")]
+    #[case("x = 4, y = 2\nb3o$3o!", "C2_4")]
+    #[case("x = 3, y = 3\nb2o$2o$bo!", "C1")]
+    fn test_symmetry_of(#[case] rle: &str, #[case] expected: &str) {
+        let name = of(&pattern(rle)).unwrap();
+        assert_eq!(expected, name);
+        assert!(name.parse::<Symmetry>().is_ok());
+    }
+
+    #[test]
+    fn test_symmetry_of_empty() {
+        assert_eq!(None, of(&Generation::new(vec![vec![false; 3]; 3])));
+    }
+
+    #[rstest]
+    #[case("x = 3, y = 1\n3o!", 2, (0, 0), "D8")]
+    #[case("x = 4, y = 2\nb3o$3o!", 2, (0, 0), "C2")]
+    #[case("x = 4, y = 4\n2bo$obo$bobo$bo!", 2, (0, 0), "D4_x")]
+    #[case("x = 3, y = 3\nbo$2bo$3o!", 4, (1, 1), "D2_x with glide reflection")]
+    #[case("x = 5, y = 4\nbo2bo$o4b$o3bo$4o!", 4, (-2, 0), "D2_+ with glide reflection")]
+    fn test_symmetry_over_time(#[case] rle: &str, #[case] period: u64, #[case] displacement: (isize, isize), #[case] expected: &str) {
+        let cycle = Cycle{start: 0, period, displacement};
+        assert_eq!(expected, over_time(&pattern(rle), None, &cycle).unwrap().to_string());
+    }
+}
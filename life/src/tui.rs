@@ -0,0 +1,735 @@
+use crate::automaton::Automaton;
+use crate::colour::{Ages, Palette};
+use crate::escape::EscapeDetector;
+use crate::generation::Generation;
+use crate::library::{self, Library};
+use crate::period::PeriodDetector;
+use crate::render::{Glyph, Renderer, Rgb};
+use crate::rle;
+use crate::rule::Rule;
+use crate::screen::Screen;
+use crate::viewport::Viewport;
+use crossterm::{
+    cursor,
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
+    },
+    execute, queue,
+    style::{Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal,
+};
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+const MAX_DELAY: Duration = Duration::from_secs(5);
+// Generations `B` rewinds by.
+const REWIND: u64 = 100;
+// Longest spent catching up on generations before drawing a frame.
+const FRAME_BUDGET: Duration = Duration::from_millis(33);
+
+/// Puts the terminal into raw mode on the alternate screen, and restores it
+/// when dropped so that quitting or panicking leaves a usable shell.
+struct Terminal;
+
+impl Terminal {
+    fn enter() -> io::Result<Terminal> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Terminal)
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), DisableMouseCapture, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Continue,
+    Redraw,
+    Quit,
+}
+
+/// Interactive runner: space pauses or resumes, `n` steps a single
+/// generation, `+` and `-` halve or double the delay, `r` reseeds and `q`
+/// quits. The arrow keys pan, `i` and `o` zoom in and out and `f` fits the
+/// whole grid on screen. `b` pauses and steps back a generation, and `B`
+/// rewinds up to a hundred, as far as the automaton remembers. `k` names
+/// the well known objects in the grid, such as gliders and blocks.
+/// Spaceships leaving the grid can be counted, and removed at its edge.
+/// With a palette, cells are coloured by their ages. Once the pattern starts
+/// repeating its period is shown, and the run can pause there.
+/// A status line under the grid shows the generation, population, rule and
+/// speed. When generations take longer than the delay, several are run
+/// between frames, and each frame only redraws the characters that changed.
+///
+/// `e` pauses and enters edit mode, where the arrow keys move a cursor over
+/// the cells, space or enter toggles the cell under it and `s` saves the grid
+/// as RLE. Clicking toggles a cell and dragging paints the same change over
+/// others. Clicks only say which character was hit, so where a character
+/// holds several cells, as with half-blocks, braille or zooming out, the
+/// mouse edits its top left cell and the rest need the cursor.
+pub struct Tui {
+    delay: Duration,
+    paused: bool,
+    reseed: Option<Box<dyn FnMut() -> Generation>>,
+    renderer: Renderer,
+    viewport: Viewport,
+    palette: Option<Palette>,
+    ages: Option<Ages>,
+    columns: u16,
+    rows: u16,
+    screen: Screen,
+    speed: f64,
+    speed_window: (Instant, u64),
+    editing: bool,
+    cursor: (usize, usize),
+    painting: Option<bool>,
+    save_file: String,
+    message: Option<String>,
+    detector: PeriodDetector,
+    stop_when_stable: bool,
+    library: Option<Library>,
+    known: Option<(Vec<Vec<bool>>, String)>,
+    escapes: Option<EscapeDetector>,
+}
+
+impl Tui {
+    pub fn new(delay: &u32, renderer: Renderer, palette: Option<Palette>, reseed: Option<Box<dyn FnMut() -> Generation>>) -> Tui {
+        let (width, height) = renderer.cell_size();
+        Tui{
+            delay: Duration::from_millis(*delay as u64),
+            paused: false,
+            reseed,
+            renderer,
+            viewport: Viewport::new(80 * width, 24 * height),
+            palette,
+            ages: None,
+            columns: 80,
+            rows: 24,
+            screen: Screen::new(),
+            speed: 0.0,
+            speed_window: (Instant::now(), 0),
+            editing: false,
+            cursor: (0, 0),
+            painting: None,
+            save_file: "pattern.rle".to_owned(),
+            message: None,
+            detector: PeriodDetector::new(),
+            stop_when_stable: false,
+            library: None,
+            known: None,
+            escapes: None,
+        }
+    }
+
+    /// Whether to pause once the pattern starts repeating.
+    pub fn set_stop_when_stable(&mut self, stop: bool) {
+        self.stop_when_stable = stop;
+    }
+
+    /// Where `s` saves the grid in edit mode.
+    pub fn save_to(&mut self, filename: &str) {
+        self.save_file = filename.to_owned();
+    }
+
+    /// Counts spaceships leaving the grid, and removes them if the detector
+    /// is set to.
+    pub fn track_escapes(&mut self, escapes: EscapeDetector) {
+        self.escapes = Some(escapes);
+    }
+
+    /// Advances the automaton, keeping the ages of its cells up to date.
+    fn step(&mut self, automaton: &mut dyn Automaton) {
+        automaton.step();
+        if let Some(escapes) = self.escapes.as_mut() {
+            escapes.observe(automaton);
+        }
+        if let Some(ages) = self.ages.as_mut() {
+            ages.update(automaton.generation());
+        }
+        let repeated = self.detector.cycle().is_none()
+            && self.detector.observe(automaton.generation_count(), automaton.generation()).is_some();
+        if repeated && self.stop_when_stable {
+            self.paused = true;
+        }
+    }
+
+    /// Looks for cycles afresh from the automaton's current generation.
+    fn restart_detector(&mut self, automaton: &dyn Automaton) {
+        self.detector = PeriodDetector::new();
+        self.detector.observe(automaton.generation_count(), automaton.generation());
+    }
+
+    /// Starts tracking ages and cycles afresh from the automaton's current
+    /// generation.
+    fn restart_tracking(&mut self, automaton: &dyn Automaton) {
+        if self.palette.is_some() {
+            self.ages = Some(Ages::new(automaton.generation()));
+        }
+        self.restart_detector(automaton);
+    }
+
+    /// Fits the viewport to a terminal of `columns` by `rows` characters,
+    /// leaving the bottom row for the status line.
+    fn resize(&mut self, columns: u16, rows: u16) {
+        let (width, height) = self.renderer.cell_size();
+        self.columns = columns;
+        self.rows = rows;
+        self.viewport.resize(columns as usize * width, rows.saturating_sub(1) as usize * height);
+    }
+
+    /// Updates the generations per second, averaged over at least a second.
+    fn measure_speed(&mut self, generation_count: u64) {
+        let (start, start_count) = self.speed_window;
+        if generation_count < start_count {
+            self.speed_window = (Instant::now(), generation_count);
+            return;
+        }
+        let elapsed = start.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            self.speed = (generation_count - start_count) as f64 / elapsed.as_secs_f64();
+            self.speed_window = (Instant::now(), generation_count);
+        }
+    }
+
+    /// Finds the known objects afresh if the grid has changed since they were
+    /// last found, however it changed.
+    fn update_known(&mut self, automaton: &dyn Automaton) {
+        let Some(library) = self.library.as_ref() else {
+            return;
+        };
+        let grid = &automaton.generation().grid;
+        if self.known.as_ref().is_some_and(|(known, _)| known == grid) {
+            return;
+        }
+        let summary = match automaton.rule().parse::<Rule>() {
+            Ok(rule) => match library.find(automaton.generation(), &rule) {
+                found if found.is_empty() => "no known objects".to_owned(),
+                found => library::summary(&found),
+            },
+            Err(_) => "objects are only named under life-like rules".to_owned(),
+        };
+        self.known = Some((grid.clone(), summary));
+    }
+
+    fn status(&self, automaton: &dyn Automaton) -> String {
+        let speed = match self.paused {
+            true => "paused".to_owned(),
+            false => format!("{:.1} gen/s", self.speed),
+        };
+        let mut status = format!(
+            "Generation {} | Population {} | {} | {}",
+            automaton.generation_count(),
+            automaton.generation().population(),
+            automaton.rule(),
+            speed,
+        );
+        if let Some(cycle) = self.detector.cycle() {
+            status += &format!(" | {}", cycle);
+        }
+        if let Some(escapes) = &self.escapes {
+            status += &format!(" | {} escaped", escapes.escapes().len());
+        }
+        if let Some((_, known)) = &self.known {
+            status += &format!(" | {}", known);
+        }
+        if self.editing {
+            status += &format!(" | editing ({}, {})", self.cursor.0, self.cursor.1);
+            let (width, height) = self.renderer.cell_size();
+            if width * height * self.viewport.zoom() > 1 {
+                status += ", clicks edit top left cells";
+            }
+        }
+        if let Some(message) = &self.message {
+            status += &format!(" | {}", message);
+        }
+        status
+    }
+
+    fn set_cell(&mut self, automaton: &mut dyn Automaton, (x, y): (usize, usize), alive: bool) {
+        automaton.set_cell(x, y, alive);
+        if let Some(ages) = self.ages.as_mut() {
+            ages.ages[y][x] = alive as i32;
+        }
+        self.restart_detector(automaton);
+    }
+
+    /// Moves the edit cursor within the grid, panning to keep it on screen.
+    fn move_cursor(&mut self, automaton: &dyn Automaton, dx: isize, dy: isize) {
+        let grid = &automaton.generation().grid;
+        let x = (self.cursor.0 as isize + dx).clamp(0, grid[0].len() as isize - 1) as usize;
+        let y = (self.cursor.1 as isize + dy).clamp(0, grid.len() as isize - 1) as usize;
+        self.cursor = (x, y);
+        if self.viewport.pixel_of(x, y).is_none() {
+            self.viewport.pan(dx, dy);
+        }
+    }
+
+    /// Handles the keys which mean something else in edit mode, returning
+    /// `None` for the rest.
+    fn handle_edit_key(&mut self, key: KeyEvent, automaton: &mut dyn Automaton) -> Option<Command> {
+        match key.code {
+            KeyCode::Char('e') | KeyCode::Esc => { self.editing = false; }
+            KeyCode::Left => self.move_cursor(automaton, -1, 0),
+            KeyCode::Right => self.move_cursor(automaton, 1, 0),
+            KeyCode::Up => self.move_cursor(automaton, 0, -1),
+            KeyCode::Down => self.move_cursor(automaton, 0, 1),
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                let (x, y) = self.cursor;
+                let alive = automaton.generation().alive(&x, &y);
+                self.set_cell(automaton, self.cursor, !alive);
+            }
+            KeyCode::Char('s') => {
+                let rule = automaton.rule();
+                self.message = Some(match rle::write_file(&self.save_file, &automaton.generation().grid, Some(&rule)) {
+                    Ok(()) => format!("saved {}", self.save_file),
+                    Err(err) => format!("could not save {}: {}", self.save_file, err),
+                });
+            }
+            _ => { return None; }
+        }
+        Some(Command::Redraw)
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent, automaton: &mut dyn Automaton) -> Command {
+        if !self.editing || event.row + 1 >= self.rows {
+            return Command::Continue;
+        }
+        let (width, height) = self.renderer.cell_size();
+        let (x, y) = self.viewport.cell_at(event.column as usize * width, event.row as usize * height);
+        let grid = &automaton.generation().grid;
+        if x < 0 || y < 0 || y as usize >= grid.len() || x as usize >= grid[0].len() {
+            return Command::Continue;
+        }
+        let cell = (x as usize, y as usize);
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let alive = !automaton.generation().alive(&cell.0, &cell.1);
+                self.painting = Some(alive);
+                self.cursor = cell;
+                self.set_cell(automaton, cell, alive);
+            }
+            MouseEventKind::Drag(MouseButton::Left) => match self.painting {
+                Some(alive) => {
+                    self.cursor = cell;
+                    self.set_cell(automaton, cell, alive);
+                }
+                None => { return Command::Continue; }
+            },
+            MouseEventKind::Up(MouseButton::Left) => {
+                self.painting = None;
+                return Command::Continue;
+            }
+            _ => { return Command::Continue; }
+        }
+        Command::Redraw
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, automaton: &mut dyn Automaton) -> Command {
+        self.message = None;
+        if self.editing {
+            if let Some(command) = self.handle_edit_key(key, automaton) {
+                return command;
+            }
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Command::Quit,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Command::Quit,
+            KeyCode::Char(' ') => {
+                self.paused = !self.paused;
+                Command::Redraw
+            }
+            KeyCode::Char('n') => {
+                self.step(automaton);
+                Command::Redraw
+            }
+            KeyCode::Char('b') | KeyCode::Char('B') => {
+                self.paused = true;
+                let count = automaton.generation_count();
+                let back = match key.code {
+                    KeyCode::Char('b') => 1,
+                    _ => REWIND,
+                }.min(automaton.remembered());
+                if back == 0 {
+                    self.message = Some("no earlier generations remembered".to_owned());
+                } else if let Err(err) = automaton.goto(count - back) {
+                    self.message = Some(err.to_owned());
+                }
+                self.restart_tracking(automaton);
+                Command::Redraw
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.delay /= 2;
+                Command::Redraw
+            }
+            KeyCode::Char('-') => {
+                self.delay = (self.delay * 2).clamp(Duration::from_millis(1), MAX_DELAY);
+                Command::Redraw
+            }
+            KeyCode::Left => {
+                self.viewport.pan(-1, 0);
+                Command::Redraw
+            }
+            KeyCode::Right => {
+                self.viewport.pan(1, 0);
+                Command::Redraw
+            }
+            KeyCode::Up => {
+                self.viewport.pan(0, -1);
+                Command::Redraw
+            }
+            KeyCode::Down => {
+                self.viewport.pan(0, 1);
+                Command::Redraw
+            }
+            KeyCode::Char('i') => {
+                self.viewport.zoom_in();
+                Command::Redraw
+            }
+            KeyCode::Char('o') => {
+                self.viewport.zoom_out();
+                Command::Redraw
+            }
+            KeyCode::Char('f') => {
+                self.viewport.fit();
+                Command::Redraw
+            }
+            KeyCode::Char('k') => {
+                self.library = match self.library {
+                    Some(_) => None,
+                    None => Some(Library::new()),
+                };
+                self.known = None;
+                Command::Redraw
+            }
+            KeyCode::Char('e') => {
+                self.editing = true;
+                self.paused = true;
+                let grid = &automaton.generation().grid;
+                self.cursor = (grid[0].len() / 2, grid.len() / 2);
+                Command::Redraw
+            }
+            KeyCode::Char('r') => match self.reseed.as_mut() {
+                Some(reseed) => {
+                    automaton.reset(reseed());
+                    self.restart_tracking(automaton);
+                    Command::Redraw
+                }
+                None => Command::Continue,
+            },
+            _ => Command::Continue,
+        }
+    }
+
+    /// Everything on screen: the visible grid and the status line under it.
+    fn frame(&mut self, automaton: &dyn Automaton) -> Vec<Vec<Glyph>> {
+        let pixels: Vec<Vec<Option<Rgb>>> = match (self.palette, self.ages.as_ref()) {
+            (Some(palette), Some(ages)) => self.viewport.sample(&ages.ages, Ages::merge).iter()
+                .map(|row| row.iter().map(|age| palette.colour(*age)).collect())
+                .collect(),
+            _ => self.viewport.view(automaton.generation()).grid.iter()
+                .map(|row| row.iter().map(|alive| alive.then_some((255, 255, 255))).collect())
+                .collect(),
+        };
+        let mut frame = self.renderer.glyphs(&pixels);
+        if self.palette.is_none() {
+            for glyph in frame.iter_mut().flatten() {
+                (glyph.fg, glyph.bg) = (None, None);
+            }
+        }
+        self.update_known(automaton);
+        let status = self.status(automaton);
+        frame.push(status.chars().chain(std::iter::repeat(' '))
+            .take(self.columns as usize)
+            .map(|ch| Glyph{ch, fg: None, bg: None})
+            .collect());
+        frame
+    }
+
+    fn draw(&mut self, stdout: &mut io::Stdout, automaton: &dyn Automaton) -> io::Result<()> {
+        self.measure_speed(automaton.generation_count());
+        let frame = self.frame(automaton);
+        queue!(stdout, cursor::Hide)?;
+        for run in self.screen.update(frame) {
+            queue!(stdout, cursor::MoveTo(run.x, run.y))?;
+            for glyph in run.glyphs {
+                if let Some(fg) = glyph.fg.zip(self.palette) {
+                    queue!(stdout, SetForegroundColor(fg.1.terminal_colour(fg.0)))?;
+                }
+                if let Some(bg) = glyph.bg.zip(self.palette) {
+                    queue!(stdout, SetBackgroundColor(bg.1.terminal_colour(bg.0)))?;
+                }
+                queue!(stdout, Print(glyph.ch))?;
+                if glyph.fg.is_some() || glyph.bg.is_some() {
+                    queue!(stdout, ResetColor)?;
+                }
+            }
+        }
+        if let Some((px, py)) = self.viewport.pixel_of(self.cursor.0, self.cursor.1).filter(|_| self.editing) {
+            let (width, height) = self.renderer.cell_size();
+            queue!(stdout, cursor::MoveTo((px / width) as u16, (py / height) as u16), cursor::Show)?;
+        }
+        stdout.flush()
+    }
+
+    pub fn run(&mut self, automaton: &mut dyn Automaton) -> io::Result<()> {
+        let _terminal = Terminal::enter()?;
+        let mut stdout = io::stdout();
+        let (columns, rows) = terminal::size()?;
+        self.resize(columns, rows);
+        self.restart_tracking(automaton);
+        let mut next_step = Instant::now() + self.delay;
+        self.draw(&mut stdout, automaton)?;
+        loop {
+            let timeout = match self.paused {
+                true => MAX_DELAY,
+                false => next_step.saturating_duration_since(Instant::now()),
+            };
+            if event::poll(timeout)? {
+                let was_paused = self.paused;
+                let was_editing = self.editing;
+                let command = match event::read()? {
+                    Event::Key(key) if key.kind == KeyEventKind::Press => self.handle_key(key, automaton),
+                    Event::Mouse(mouse) => self.handle_mouse(mouse, automaton),
+                    Event::Resize(columns, rows) => {
+                        self.resize(columns, rows);
+                        Command::Redraw
+                    }
+                    _ => Command::Continue,
+                };
+                match command {
+                    Command::Quit => { return Ok(()); }
+                    Command::Redraw => { self.draw(&mut stdout, automaton)?; }
+                    Command::Continue => {}
+                }
+                if was_paused && !self.paused {
+                    next_step = Instant::now() + self.delay;
+                }
+                match (was_editing, self.editing) {
+                    (false, true) => execute!(stdout, EnableMouseCapture)?,
+                    (true, false) => execute!(stdout, DisableMouseCapture)?,
+                    _ => {}
+                }
+            } else if !self.paused {
+                let frame_start = Instant::now();
+                while !self.paused && next_step <= Instant::now() && frame_start.elapsed() < FRAME_BUDGET {
+                    self.step(automaton);
+                    next_step += self.delay;
+                }
+                // Give up on generations still owed rather than falling further behind.
+                next_step = next_step.max(Instant::now());
+                self.draw(&mut stdout, automaton)?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colour::{Depth, Scheme};
+    use crate::game::Game;
+    use crate::margolus::BlockGame;
+    use rstest::rstest;
+
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn blinker() -> Game {
+        Game::new(Generation::new(vec![
+            vec![false, true, false],
+            vec![false, true, false],
+            vec![false, true, false],
+        ]), None)
+    }
+
+    #[rstest]
+    #[case(KeyCode::Char('q'), Command::Quit)]
+    #[case(KeyCode::Esc, Command::Quit)]
+    #[case(KeyCode::Char('x'), Command::Continue)]
+    #[case(KeyCode::Char('r'), Command::Continue)] // nothing to reseed from
+    fn test_tui_handle_key(#[case] code: KeyCode, #[case] expected: Command) {
+        let mut tui = Tui::new(&16, Renderer::HalfBlock, None, None);
+        assert_eq!(expected, tui.handle_key(press(code), &mut blinker()));
+    }
+
+    #[test]
+    fn test_tui_pause_and_step() {
+        let mut tui = Tui::new(&16, Renderer::HalfBlock, None, None);
+        let mut game = blinker();
+        tui.handle_key(press(KeyCode::Char(' ')), &mut game);
+        assert!(tui.paused);
+        tui.handle_key(press(KeyCode::Char('n')), &mut game);
+        assert_eq!(vec![false, false, false], game.generation().grid[0]);
+        tui.handle_key(press(KeyCode::Char(' ')), &mut game);
+        assert!(!tui.paused);
+    }
+
+    #[rstest]
+    #[case(16, '+', 8)]
+    #[case(0, '+', 0)]
+    #[case(16, '-', 32)]
+    #[case(0, '-', 1)]
+    #[case(4000, '-', 5000)]
+    fn test_tui_change_delay(#[case] delay: u32, #[case] key: char, #[case] expected: u64) {
+        let mut tui = Tui::new(&delay, Renderer::HalfBlock, None, None);
+        tui.handle_key(press(KeyCode::Char(key)), &mut blinker());
+        assert_eq!(Duration::from_millis(expected), tui.delay);
+    }
+
+    #[test]
+    fn test_tui_reseed() {
+        let reseed = || Generation::new(vec![vec![true; 3]; 3]);
+        let mut tui = Tui::new(&16, Renderer::HalfBlock, None, Some(Box::new(reseed)));
+        let mut game = blinker();
+        assert_eq!(Command::Redraw, tui.handle_key(press(KeyCode::Char('r')), &mut game));
+        assert_eq!(vec![vec![true; 3]; 3], game.generation().grid);
+    }
+
+    #[test]
+    fn test_tui_status() {
+        let mut tui = Tui::new(&16, Renderer::HalfBlock, None, None);
+        let mut game = blinker();
+        tui.handle_key(press(KeyCode::Char('n')), &mut game);
+        tui.handle_key(press(KeyCode::Char('n')), &mut game);
+        assert_eq!("Generation 2 | Population 3 | B3/S23 | 0.0 gen/s", tui.status(&game));
+        tui.handle_key(press(KeyCode::Char(' ')), &mut game);
+        assert_eq!("Generation 2 | Population 3 | B3/S23 | paused", tui.status(&game));
+    }
+
+    #[test]
+    fn test_tui_frame() {
+        let mut tui = Tui::new(&16, Renderer::Ascii, None, None);
+        tui.resize(9, 4);
+        tui.viewport.fit();
+        let frame: Vec<String> = tui.frame(&blinker()).iter()
+            .map(|line| line.iter().map(|glyph| glyph.ch).collect())
+            .collect();
+        assert_eq!(vec!["....#....", "....#....", "....#....", "Generatio"], frame);
+    }
+
+    #[test]
+    fn test_tui_names_known_objects() {
+        let mut tui = Tui::new(&16, Renderer::Ascii, None, None);
+        let mut game = blinker();
+        tui.handle_key(press(KeyCode::Char('k')), &mut game);
+        tui.frame(&game);
+        assert!(tui.status(&game).ends_with(" | blinker"));
+        tui.handle_key(press(KeyCode::Char('k')), &mut game);
+        tui.frame(&game);
+        assert_eq!("Generation 0 | Population 3 | B3/S23 | 0.0 gen/s", tui.status(&game));
+    }
+
+    #[test]
+    fn test_tui_names_gun_and_follows_reseed() {
+        let gun = rle::parse_file(include_str!("../data/glidergun.rle")).unwrap().0;
+        let reseed = Box::new(|| Generation::new(vec![vec![true; 2]; 2]));
+        let mut tui = Tui::new(&16, Renderer::Ascii, None, Some(reseed));
+        let mut game = Game::new(Generation::new(gun), None);
+        tui.handle_key(press(KeyCode::Char('k')), &mut game);
+        tui.frame(&game);
+        assert!(tui.status(&game).ends_with(" | gosper glider gun"));
+        tui.handle_key(press(KeyCode::Char('r')), &mut game);
+        tui.frame(&game);
+        assert!(tui.status(&game).ends_with(" | block"));
+    }
+
+    #[test]
+    fn test_tui_edit_with_keys() {
+        let mut tui = Tui::new(&16, Renderer::HalfBlock, None, None);
+        let mut game = blinker();
+        tui.handle_key(press(KeyCode::Char('e')), &mut game);
+        assert!(tui.editing && tui.paused);
+        assert_eq!((1, 1), tui.cursor);
+        tui.handle_key(press(KeyCode::Left), &mut game);
+        tui.handle_key(press(KeyCode::Left), &mut game);
+        tui.handle_key(press(KeyCode::Char(' ')), &mut game);
+        tui.handle_key(press(KeyCode::Up), &mut game);
+        tui.handle_key(press(KeyCode::Right), &mut game);
+        tui.handle_key(press(KeyCode::Enter), &mut game);
+        assert_eq!(vec![vec![false, false, false], vec![true, true, false]], game.generation().grid[..2]);
+        assert!(tui.status(&game).ends_with(" | editing (1, 0), clicks edit top left cells"));
+        assert_eq!(Command::Redraw, tui.handle_key(press(KeyCode::Esc), &mut game));
+        assert!(!tui.editing);
+    }
+
+    #[test]
+    fn test_tui_edit_save_round_trip() {
+        let filename = std::env::temp_dir().join("life_test_tui_save.rle");
+        let filename = filename.to_str().unwrap();
+        let grid = vec![vec![true, false, true, false], vec![false, true, true, false]];
+        let mut tui = Tui::new(&16, Renderer::HalfBlock, None, None);
+        tui.save_to(filename);
+        let mut game = BlockGame::new(Generation::new(grid.clone()), "critters".parse().unwrap());
+        tui.handle_key(press(KeyCode::Char('e')), &mut game);
+        tui.handle_key(press(KeyCode::Char('s')), &mut game);
+        assert_eq!(Some(format!("saved {}", filename)), tui.message);
+        assert_eq!((grid, None), rle::read_file(filename).unwrap());
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_tui_edit_with_mouse() {
+        let mut tui = Tui::new(&16, Renderer::Ascii, None, None);
+        let mut game = blinker();
+        tui.resize(3, 4);
+        tui.frame(&game);
+        let mouse = |kind, column| MouseEvent{kind, column, row: 0, modifiers: KeyModifiers::NONE};
+        assert_eq!(Command::Continue, tui.handle_mouse(mouse(MouseEventKind::Down(MouseButton::Left), 0), &mut game));
+        tui.handle_key(press(KeyCode::Char('e')), &mut game);
+        tui.handle_mouse(mouse(MouseEventKind::Down(MouseButton::Left), 0), &mut game);
+        tui.handle_mouse(mouse(MouseEventKind::Drag(MouseButton::Left), 1), &mut game);
+        tui.handle_mouse(mouse(MouseEventKind::Drag(MouseButton::Left), 2), &mut game);
+        tui.handle_mouse(mouse(MouseEventKind::Up(MouseButton::Left), 2), &mut game);
+        assert_eq!(vec![true, true, true], game.generation().grid[0]);
+        assert_eq!((2, 0), tui.cursor);
+        assert!(tui.status(&game).ends_with(" | editing (2, 0)"));
+    }
+
+    #[test]
+    fn test_tui_rewind() {
+        let mut tui = Tui::new(&16, Renderer::HalfBlock, None, None);
+        let mut game = blinker();
+        game.set_history_memory(1000);
+        for _ in 0..5 {
+            tui.handle_key(press(KeyCode::Char('n')), &mut game);
+        }
+        tui.handle_key(press(KeyCode::Char('b')), &mut game);
+        assert_eq!((4, true), (game.generation_count(), tui.paused));
+        tui.handle_key(press(KeyCode::Char('B')), &mut game);
+        assert_eq!(0, game.generation_count());
+        tui.handle_key(press(KeyCode::Char('b')), &mut game);
+        assert_eq!(Some("no earlier generations remembered".to_owned()), tui.message);
+    }
+
+    #[test]
+    fn test_tui_stop_when_stable() {
+        let mut tui = Tui::new(&16, Renderer::HalfBlock, None, None);
+        tui.set_stop_when_stable(true);
+        let mut game = blinker();
+        tui.restart_tracking(&game);
+        tui.step(&mut game);
+        assert!(!tui.paused);
+        tui.step(&mut game);
+        assert!(tui.paused);
+        assert_eq!("Generation 2 | Population 3 | B3/S23 | paused | period 2 from generation 0", tui.status(&game));
+    }
+
+    #[test]
+    fn test_tui_step_tracks_ages() {
+        let palette = Palette{scheme: Scheme::Status, depth: Depth::Ansi256, trail: 8};
+        let mut tui = Tui::new(&16, Renderer::HalfBlock, Some(palette), None);
+        let mut game = blinker();
+        tui.restart_tracking(&game);
+        tui.handle_key(press(KeyCode::Char('n')), &mut game);
+        assert_eq!(vec![vec![0, -1, 0], vec![1, 2, 1]], tui.ages.unwrap().ages[..2]);
+    }
+}
@@ -0,0 +1,407 @@
+use crate::automaton::Automaton;
+use crate::generation::Generation;
+use phf::{Map, phf_map};
+use std::fmt;
+use std::str::FromStr;
+
+static NAMED_TURMITES: Map<&'static str, &'static str> = phf_map! {
+    "langton" => "RL",
+    "chaotic" => "RLR",
+    "symmetric" => "LLRR",
+    "square" => "LRRRRRLLR",
+    "highway" => "LLRRRLRLRLLR",
+    "triangle" => "RRLLLRLLLRRR",
+    "fibonacci" => "{{{1,8,1},{1,8,1}},{{1,2,1},{0,1,0}}}",
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Turn {
+    None,
+    Right,
+    Back,
+    Left,
+}
+
+impl Turn {
+    /// Decodes the turn codes used in Ed Pegg's turmite tables.
+    fn from_code(code: u32) -> Option<Turn> {
+        match code {
+            1 => Some(Turn::None),
+            2 => Some(Turn::Right),
+            4 => Some(Turn::Back),
+            8 => Some(Turn::Left),
+            _ => None,
+        }
+    }
+
+    fn from_letter(letter: char) -> Option<Turn> {
+        match letter {
+            'N' => Some(Turn::None),
+            'R' => Some(Turn::Right),
+            'U' => Some(Turn::Back),
+            'L' => Some(Turn::Left),
+            _ => None,
+        }
+    }
+
+    fn letter(&self) -> char {
+        match self {
+            Turn::None => 'N',
+            Turn::Right => 'R',
+            Turn::Back => 'U',
+            Turn::Left => 'L',
+        }
+    }
+
+    fn apply(&self, direction: u8) -> u8 {
+        (direction + *self as u8) % 4
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Transition {
+    colour: u8,
+    turn: Turn,
+    state: usize,
+}
+
+/// A turmite rule, indexed by ant state and then the colour under the ant.
+/// Accepts Langton's ant style turn strings (`RL`, `LLRR`), where each colour
+/// cycles to the next, or Ed Pegg's tables (`{{{1,2,0},{0,8,0}}}`) listing the
+/// colour to write, the turn to make and the next state.
+#[derive(Debug, PartialEq)]
+pub struct TurmiteRule {
+    table: Vec<Vec<Transition>>,
+}
+
+fn parse_turns(turns: &str) -> Result<TurmiteRule, &'static str> {
+    let colours = turns.len();
+    if colours > 256 {
+        return Err("Turmites can have at most 256 colours");
+    }
+    let mut transitions = Vec::new();
+    for (colour, letter) in turns.chars().enumerate() {
+        let turn = Turn::from_letter(letter).ok_or("Unrecognised content in rulestring")?;
+        transitions.push(Transition{colour: ((colour + 1) % colours) as u8, turn, state: 0});
+    }
+    Ok(TurmiteRule{table: vec![transitions]})
+}
+
+fn parse_table(table: &str) -> Result<TurmiteRule, &'static str> {
+    let mut states: Vec<Vec<Transition>> = Vec::new();
+    let mut numbers: Vec<u32> = Vec::new();
+    let mut accumulator = "".to_owned();
+    let mut depth = 0;
+    for ch in table.chars().filter(|ch| !ch.is_whitespace()) {
+        match ch {
+            '{' => {
+                depth += 1;
+                if depth == 2 {
+                    states.push(Vec::new());
+                }
+                if depth > 3 {
+                    return Err("Turmite tables nest three levels deep");
+                }
+            }
+            '}' | ',' => {
+                if !accumulator.is_empty() {
+                    numbers.push(accumulator.parse().map_err(|_| "Unrecognised number in turmite table")?);
+                    accumulator = "".to_owned();
+                }
+                if ch == '}' {
+                    if depth == 3 {
+                        let [colour, turn, state] = numbers[..] else {
+                            return Err("Turmite transitions need a colour, turn and state");
+                        };
+                        let turn = Turn::from_code(turn).ok_or("Turns must be one of 1, 2, 4 or 8")?;
+                        if colour > 255 {
+                            return Err("Turmites can have at most 256 colours");
+                        }
+                        states.last_mut().unwrap().push(Transition{colour: colour as u8, turn, state: state as usize});
+                        numbers.clear();
+                    } else if !numbers.is_empty() {
+                        return Err("Turmite transitions must be in braces");
+                    }
+                    depth -= 1;
+                }
+            }
+            '0'..='9' => {
+                accumulator.push(ch);
+            }
+            _ => { return Err("Unrecognised content in rulestring"); }
+        }
+    }
+    if depth != 0 || states.is_empty() {
+        return Err("Unbalanced braces in turmite table");
+    }
+    let colours = states[0].len();
+    if colours < 2 {
+        return Err("Turmites need at least two colours");
+    }
+    for transitions in &states {
+        if transitions.len() != colours {
+            return Err("Every turmite state needs a transition for each colour");
+        }
+        for transition in transitions {
+            if transition.colour as usize >= colours || transition.state >= states.len() {
+                return Err("Turmite transition refers to a missing colour or state");
+            }
+        }
+    }
+    Ok(TurmiteRule{table: states})
+}
+
+impl FromStr for TurmiteRule {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rulestring = s;
+        if NAMED_TURMITES.contains_key(rulestring) {
+            rulestring = NAMED_TURMITES[rulestring];
+        }
+        if rulestring.starts_with('{') {
+            parse_table(rulestring)
+        } else if rulestring.len() < 2 {
+            Err("Turmites need at least two colours")
+        } else {
+            parse_turns(rulestring)
+        }
+    }
+}
+
+impl fmt::Display for TurmiteRule {
+    /// Writes turn strings where the rule is one, and Pegg tables otherwise.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let colours = self.table[0].len();
+        let cycles = self.table.len() == 1 && self.table[0].iter().enumerate()
+            .all(|(colour, transition)| transition.colour as usize == (colour + 1) % colours && transition.state == 0);
+        if cycles {
+            return self.table[0].iter()
+                .try_for_each(|transition| write!(f, "{}", transition.turn.letter()));
+        }
+        let states: Vec<String> = self.table.iter().map(|transitions| {
+            let transitions: Vec<String> = transitions.iter()
+                .map(|transition| format!("{{{},{},{}}}", transition.colour, 1 << transition.turn as u8, transition.state))
+                .collect();
+            format!("{{{}}}", transitions.join(","))
+        }).collect();
+        write!(f, "{{{}}}", states.join(","))
+    }
+}
+
+impl TurmiteRule {
+    pub fn apply(&self, state: usize, colour: u8) -> Transition {
+        self.table[state][colour as usize]
+    }
+}
+
+/// An ant on the grid, facing 0 = north, 1 = east, 2 = south or 3 = west.
+#[derive(Debug, PartialEq)]
+pub struct Ant {
+    x: usize,
+    y: usize,
+    direction: u8,
+    state: usize,
+}
+
+impl Ant {
+    pub fn new(x: usize, y: usize, direction: u8) -> Ant {
+        Ant{x, y, direction, state: 0}
+    }
+}
+
+/// Runs turmites over a grid of colours. Colour 0 is shown as dead and every
+/// other colour as alive; ants that walk off the edge are removed.
+pub struct Turmite {
+    colours: Vec<Vec<u8>>,
+    current_generation: Generation,
+    rule: TurmiteRule,
+    ants: Vec<Ant>,
+    moves: usize,
+    generation_count: u64,
+}
+
+impl Turmite {
+    pub fn new(width: usize, height: usize, rule: TurmiteRule, ants: Vec<Ant>, moves: usize) -> Turmite {
+        Turmite{
+            colours: vec![vec![0; width]; height],
+            current_generation: Generation::new(vec![vec![false; width]; height]),
+            rule,
+            ants,
+            moves,
+            generation_count: 0,
+        }
+    }
+
+    /// `count` ants: the first starts in the centre facing north, any others
+    /// are scattered.
+    pub fn scatter(rng: &fastrand::Rng, width: usize, height: usize, count: usize) -> Vec<Ant> {
+        (0..count).map(|index| match index {
+            0 => Ant::new(width / 2, height / 2, 0),
+            _ => Ant::new(rng.usize(..width), rng.usize(..height), rng.u8(..4)),
+        }).collect()
+    }
+
+    fn move_ants(&mut self) {
+        let height = self.colours.len();
+        let width = self.colours[0].len();
+        for ant in self.ants.iter_mut() {
+            let transition = self.rule.apply(ant.state, self.colours[ant.y][ant.x]);
+            self.colours[ant.y][ant.x] = transition.colour;
+            self.current_generation.grid[ant.y][ant.x] = transition.colour != 0;
+            ant.state = transition.state;
+            ant.direction = transition.turn.apply(ant.direction);
+            match ant.direction {
+                0 => { ant.y = ant.y.wrapping_sub(1); }
+                1 => { ant.x += 1; }
+                2 => { ant.y += 1; }
+                _ => { ant.x = ant.x.wrapping_sub(1); }
+            }
+        }
+        self.ants.retain(|ant| ant.x < width && ant.y < height);
+    }
+}
+
+impl Automaton for Turmite {
+    fn generation(&self) -> &Generation {
+        &self.current_generation
+    }
+
+    fn generation_count(&self) -> u64 {
+        self.generation_count
+    }
+
+    fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    fn step(&mut self) {
+        for _ in 0..self.moves {
+            self.move_ants();
+        }
+        self.generation_count += 1;
+    }
+
+    /// Paints the cell in colour 1, or clears it to colour 0.
+    fn set_cell(&mut self, x: usize, y: usize, alive: bool) {
+        self.colours[y][x] = alive as u8;
+        self.current_generation.grid[y][x] = alive;
+    }
+
+    /// Repaints the grid from `generation` in colour 1, leaving the ants be.
+    fn reset(&mut self, generation: Generation) {
+        self.colours = generation.grid.iter()
+            .map(|row| row.iter().map(|alive| *alive as u8).collect())
+            .collect();
+        self.current_generation = generation;
+        self.generation_count = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    type Grid = Vec<Vec<bool>>;
+
+    fn langton() -> TurmiteRule {
+        TurmiteRule{table: vec![vec![
+            Transition{colour: 1, turn: Turn::Right, state: 0},
+            Transition{colour: 0, turn: Turn::Left, state: 0},
+        ]]}
+    }
+
+    #[rstest]
+    #[case("RL", langton())]
+    #[case("langton", langton())]
+    #[case("{{{1, 2, 0}, {0, 8, 0}}}", langton())]
+    #[case(
+        "fibonacci",
+        TurmiteRule{table: vec![
+            vec![Transition{colour: 1, turn: Turn::Left, state: 1}, Transition{colour: 1, turn: Turn::Left, state: 1}],
+            vec![Transition{colour: 1, turn: Turn::Right, state: 1}, Transition{colour: 0, turn: Turn::None, state: 0}],
+        ]}
+    )]
+    fn test_turmite_rule_from_str_ok(#[case] rulestring: &str, #[case] expected: TurmiteRule) {
+        assert_eq!(Ok(expected), rulestring.parse());
+    }
+
+    #[rstest]
+    #[case("R", "Turmites need at least two colours")]
+    #[case("RX", "Unrecognised content in rulestring")]
+    #[case("{{{1,2,0},{0,8,0}}", "Unbalanced braces in turmite table")]
+    #[case("{{{1,3,0},{0,8,0}}}", "Turns must be one of 1, 2, 4 or 8")]
+    #[case("{{{1,2},{0,8,0}}}", "Turmite transitions need a colour, turn and state")]
+    #[case("{{{1,2,1},{0,8,0}}}", "Turmite transition refers to a missing colour or state")]
+    #[case("{{{1,2,0},{0,8,0}},{{1,2,0}}}", "Every turmite state needs a transition for each colour")]
+    #[case("{{1,2,0}}", "Turmite transitions must be in braces")]
+    #[case("{{}}", "Turmites need at least two colours")]
+    #[case("{{{0,2,0}}}", "Turmites need at least two colours")]
+    fn test_turmite_rule_from_str_err(#[case] rulestring: &str, #[case] expected: &str) {
+        assert_eq!(Err(expected), rulestring.parse::<TurmiteRule>());
+    }
+
+    #[rstest]
+    #[case("langton", "RL")]
+    #[case("LLRR", "LLRR")]
+    #[case("{{{1,2,0},{0,8,0}}}", "RL")]
+    #[case("fibonacci", "{{{1,8,1},{1,8,1}},{{1,2,1},{0,1,0}}}")]
+    fn test_turmite_rule_display(#[case] rulestring: &str, #[case] expected: &str) {
+        assert_eq!(expected, rulestring.parse::<TurmiteRule>().unwrap().to_string());
+    }
+
+    #[rstest]
+    #[case(
+        1,
+        vec![
+            vec![false, false, false],
+            vec![false, true, false],
+            vec![false, false, false],
+        ],
+        Ant{x: 2, y: 1, direction: 1, state: 0},
+    )]
+    #[case(
+        4,
+        vec![
+            vec![false, false, false],
+            vec![false, true, true],
+            vec![false, true, true],
+        ],
+        Ant{x: 1, y: 1, direction: 0, state: 0},
+    )]
+    #[case(
+        5, // back on a black cell, so the ant turns left and clears it
+        vec![
+            vec![false, false, false],
+            vec![false, false, true],
+            vec![false, true, true],
+        ],
+        Ant{x: 0, y: 1, direction: 3, state: 0},
+    )]
+    fn test_turmite_langtons_ant(#[case] moves: usize, #[case] expected: Grid, #[case] ant: Ant) {
+        let mut turmite = Turmite::new(3, 3, langton(), vec![Ant::new(1, 1, 0)], moves);
+        turmite.step();
+        assert_eq!(expected, turmite.generation().grid);
+        assert_eq!(vec![ant], turmite.ants);
+    }
+
+    #[test]
+    fn test_turmite_ant_leaves_grid() {
+        let mut turmite = Turmite::new(1, 1, langton(), vec![Ant::new(0, 0, 0)], 1);
+        turmite.step();
+        assert!(turmite.ants.is_empty());
+        assert_eq!(vec![vec![true]], turmite.generation().grid);
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(1)]
+    #[case(5)]
+    fn test_turmite_scatter(#[case] count: usize) {
+        let ants = Turmite::scatter(&fastrand::Rng::with_seed(1), 9, 7, count);
+        assert_eq!(count, ants.len());
+        assert_eq!(ants.first(), (count > 0).then(|| Ant::new(4, 3, 0)).as_ref());
+        assert!(ants.iter().all(|ant| ant.x < 9 && ant.y < 7));
+    }
+}
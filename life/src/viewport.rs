@@ -0,0 +1,230 @@
+use crate::generation::Generation;
+
+/// The part of the grid shown on screen. Each screen pixel (a dot of the
+/// renderer's glyphs) stands for a `zoom` by `zoom` square of cells, and is
+/// lit when any of them is alive.
+#[derive(Debug, PartialEq)]
+pub struct Viewport {
+    x: isize,
+    y: isize,
+    zoom: usize,
+    fit: bool,
+    width: usize,
+    height: usize,
+}
+
+impl Viewport {
+    /// A viewport `width` by `height` pixels which starts fitted to the grid.
+    pub fn new(width: usize, height: usize) -> Viewport {
+        Viewport{x: 0, y: 0, zoom: 1, fit: true, width, height}
+    }
+
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let centre = self.centre();
+        self.width = width.max(1);
+        self.height = height.max(1);
+        self.centre_on(centre);
+    }
+
+    fn centre(&self) -> (isize, isize) {
+        let zoom = self.zoom as isize;
+        (self.x + self.width as isize * zoom / 2, self.y + self.height as isize * zoom / 2)
+    }
+
+    fn centre_on(&mut self, (x, y): (isize, isize)) {
+        let zoom = self.zoom as isize;
+        self.x = x - self.width as isize * zoom / 2;
+        self.y = y - self.height as isize * zoom / 2;
+    }
+
+    /// Moves by a quarter of the screen in the given direction.
+    pub fn pan(&mut self, dx: isize, dy: isize) {
+        let zoom = self.zoom as isize;
+        self.x += dx * (self.width as isize / 4).max(1) * zoom;
+        self.y += dy * (self.height as isize / 4).max(1) * zoom;
+        self.fit = false;
+    }
+
+    /// Cells across each pixel.
+    pub fn zoom(&self) -> usize {
+        self.zoom
+    }
+
+    pub fn zoom_in(&mut self) {
+        let centre = self.centre();
+        self.zoom = (self.zoom / 2).max(1);
+        self.centre_on(centre);
+        self.fit = false;
+    }
+
+    pub fn zoom_out(&mut self) {
+        let centre = self.centre();
+        self.zoom *= 2;
+        self.centre_on(centre);
+        self.fit = false;
+    }
+
+    pub fn fit(&mut self) {
+        self.fit = true;
+    }
+
+    /// The smallest zoom showing the whole grid, centred on screen.
+    fn fit_to(&mut self, grid_width: usize, grid_height: usize) {
+        self.zoom = grid_width.div_ceil(self.width).max(grid_height.div_ceil(self.height)).max(1);
+        self.centre_on(((grid_width / 2) as isize, (grid_height / 2) as isize));
+    }
+
+    /// The cell at the top left of pixel `(px, py)`, which may be off the grid.
+    pub fn cell_at(&self, px: usize, py: usize) -> (isize, isize) {
+        let zoom = self.zoom as isize;
+        (self.x + px as isize * zoom, self.y + py as isize * zoom)
+    }
+
+    /// The pixel showing cell `(x, y)`, if it is on screen.
+    pub fn pixel_of(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        let zoom = self.zoom as isize;
+        let px = (x as isize - self.x).div_euclid(zoom);
+        let py = (y as isize - self.y).div_euclid(zoom);
+        match (0..self.width as isize).contains(&px) && (0..self.height as isize).contains(&py) {
+            true => Some((px as usize, py as usize)),
+            false => None,
+        }
+    }
+
+    /// Samples the visible part of `generation` down to one cell per pixel.
+    pub fn view(&mut self, generation: &Generation) -> Generation {
+        Generation::new(self.sample(&generation.grid, |a, b| a || b))
+    }
+
+    /// Samples any per-cell values, folding each pixel's block of cells
+    /// together with `merge`. Pixels off the grid get the default value.
+    pub fn sample<T: Copy + Default>(&mut self, cells: &[Vec<T>], merge: impl Fn(T, T) -> T) -> Vec<Vec<T>> {
+        let grid_height = cells.len();
+        let grid_width = cells.first().map_or(0, |row| row.len());
+        if self.fit {
+            self.fit_to(grid_width, grid_height);
+        }
+        let zoom = self.zoom as isize;
+        let mut pixels = vec![vec![T::default(); self.width]; self.height];
+        for (py, row) in pixels.iter_mut().enumerate() {
+            let top = (self.y + py as isize * zoom).max(0);
+            let bottom = (self.y + (py as isize + 1) * zoom).min(grid_height as isize);
+            for (px, pixel) in row.iter_mut().enumerate() {
+                let left = (self.x + px as isize * zoom).max(0);
+                let right = (self.x + (px as isize + 1) * zoom).min(grid_width as isize);
+                for y in top..bottom {
+                    for x in left..right {
+                        *pixel = merge(*pixel, cells[y as usize][x as usize]);
+                    }
+                }
+            }
+        }
+        pixels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    type Grid = Vec<Vec<bool>>;
+
+    fn glider() -> Generation {
+        Generation::new(vec![
+            vec![false, true, false, false],
+            vec![false, false, true, false],
+            vec![true, true, true, false],
+            vec![false, false, false, false],
+        ])
+    }
+
+    #[rstest]
+    #[case(
+        Viewport{x: 0, y: 0, zoom: 1, fit: false, width: 3, height: 2},
+        vec![
+            vec![false, true, false],
+            vec![false, false, true],
+        ],
+    )]
+    #[case(
+        Viewport{x: 2, y: 1, zoom: 1, fit: false, width: 3, height: 2},
+        vec![
+            vec![true, false, false],
+            vec![true, false, false],
+        ],
+    )]
+    #[case(
+        Viewport{x: -1, y: -1, zoom: 2, fit: false, width: 3, height: 3},
+        vec![
+            vec![false, true, false],
+            vec![true, true, false],
+            vec![false, false, false],
+        ],
+    )]
+    #[case(
+        Viewport::new(2, 2),
+        vec![
+            vec![true, true],
+            vec![true, true],
+        ],
+    )]
+    #[case(
+        Viewport::new(1, 1),
+        vec![
+            vec![true],
+        ],
+    )]
+    fn test_viewport_view(#[case] mut viewport: Viewport, #[case] expected: Grid) {
+        assert_eq!(expected, viewport.view(&glider()).grid);
+    }
+
+    #[rstest]
+    #[case((0, 0), (-1, -1))]
+    #[case((2, 1), (3, 1))]
+    fn test_viewport_cell_at(#[case] pixel: (usize, usize), #[case] expected: (isize, isize)) {
+        let viewport = Viewport{x: -1, y: -1, zoom: 2, fit: false, width: 3, height: 3};
+        assert_eq!(expected, viewport.cell_at(pixel.0, pixel.1));
+    }
+
+    #[rstest]
+    #[case((0, 0), Some((0, 0)))]
+    #[case((3, 2), Some((2, 1)))]
+    #[case((5, 0), None)]
+    fn test_viewport_pixel_of(#[case] cell: (usize, usize), #[case] expected: Option<(usize, usize)>) {
+        let viewport = Viewport{x: -1, y: -1, zoom: 2, fit: false, width: 3, height: 3};
+        assert_eq!(expected, viewport.pixel_of(cell.0, cell.1));
+    }
+
+    #[test]
+    fn test_viewport_fit_zoom() {
+        let mut viewport = Viewport::new(80, 48);
+        viewport.view(&Generation::new(vec![vec![false; 200]; 100]));
+        assert_eq!((3, -20, -22), (viewport.zoom, viewport.x, viewport.y));
+    }
+
+    #[test]
+    fn test_viewport_zoom_keeps_centre() {
+        let mut viewport = Viewport{x: 0, y: 0, zoom: 2, fit: true, width: 10, height: 10};
+        viewport.zoom_out();
+        assert_eq!((4, -10, -10, false), (viewport.zoom, viewport.x, viewport.y, viewport.fit));
+        viewport.zoom_in();
+        viewport.zoom_in();
+        viewport.zoom_in();
+        assert_eq!((1, 5, 5), (viewport.zoom, viewport.x, viewport.y));
+    }
+
+    #[test]
+    fn test_viewport_pan() {
+        let mut viewport = Viewport{x: 0, y: 0, zoom: 2, fit: true, width: 8, height: 4};
+        viewport.pan(1, -1);
+        assert_eq!((4, -2, false), (viewport.x, viewport.y, viewport.fit));
+    }
+
+    #[test]
+    fn test_viewport_resize_keeps_centre() {
+        let mut viewport = Viewport{x: 0, y: 0, zoom: 1, fit: false, width: 10, height: 10};
+        viewport.resize(20, 4);
+        assert_eq!((-5, 3), (viewport.x, viewport.y));
+    }
+}